@@ -0,0 +1,68 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use cubers::{Cube, Metric, MoveSequence, Solver, Turnable};
+
+const SCRAMBLES: [(&str, Metric); 3] = [
+    ("R U2 F' L D B2 R2", Metric::Half),
+    ("R U F' L D' B R' U", Metric::Quarter),
+    ("R M' U E2 F S'", Metric::Slice),
+];
+
+fn scrambled(scramble: &str) -> Cube {
+    let moves: MoveSequence = scramble.parse().unwrap();
+    let mut cube = Cube::default();
+    cube.perform_all(&moves.0);
+    cube
+}
+
+fn bfs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bfs");
+    group.sample_size(10);
+
+    for (scramble, metric) in SCRAMBLES {
+        let cube = scrambled(scramble);
+
+        // the number of generated positions doesn't change between runs, so report it once
+        let solver = Solver::with_metric(metric);
+        let mut search = solver.search(cube).unwrap();
+        search.solve(10).unwrap().unwrap();
+        let stats = search.stats();
+        let branching = (0..stats.expanded.len())
+            .map(|depth| format!("{:.2}", stats.branching_factor(depth)))
+            .collect::<Vec<_>>();
+        eprintln!("{metric:?} \"{scramble}\": branching factor per depth {branching:?}");
+        if metric == Metric::Half {
+            // 18 moves at the start, then about 13.35 once redundant sequences are skipped
+            for depth in 1..stats.expanded.len() {
+                let branching = stats.branching_factor(depth);
+                assert!(
+                    (13.2..13.6).contains(&branching),
+                    "branching factor {branching:.2} at depth {depth}"
+                );
+            }
+        }
+
+        // a new solver has to search the solved cube's front as well
+        group.bench_with_input(
+            BenchmarkId::new(format!("{metric:?}/cold"), scramble),
+            &cube,
+            |b, &cube| {
+                b.iter_batched_ref(
+                    || Solver::with_metric(metric),
+                    |solver| solver.solve(cube, 10),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+        // the solver above has kept the layers of the solved cube's front, so only the scramble's
+        // front is searched
+        group.bench_with_input(
+            BenchmarkId::new(format!("{metric:?}/cached"), scramble),
+            &cube,
+            |b, &cube| b.iter(|| solver.solve(cube, 10)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bfs);
+criterion_main!(benches);
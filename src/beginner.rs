@@ -0,0 +1,1081 @@
+//! Solving 3x3 rubiks cubes layer by layer, the way most people learn it.
+//!
+//! The beginner's method solves the white face first, as a cross and then its corners, then the edges of
+//! the middle layer and finally the yellow face, again as a cross and then its corners. Every stage only
+//! needs one or two short algorithms, and every step explains which piece it solves and why the moves
+//! work.
+
+use std::fmt::Display;
+
+use crate::{
+    cube::{Color, Cube, ValidationError},
+    cubie::{to_move, CubieCube, CORNER_FACELETS, EDGE_FACELETS, FACE_TURNS, MOVE_COUNT},
+    method::{distances, edge_code, frames, index, piece_moves, PieceMoves, CROSS},
+    notation::{parse, MoveSequence},
+    turn::{Move, Turnable},
+};
+
+/// Inserts a white corner that sits above its slot at the front right, when repeated one, three or five
+/// times depending on where its white sticker faces.
+const CORNER_INSERT: &str = "R U R' U'";
+
+/// Lifts whatever corner is in the front right slot into the top layer.
+const CORNER_OUT: &str = "R U R'";
+
+/// Inserts the edge at the front of the top layer into the front right slot.
+const RIGHT_INSERT: &str = "U R U' R' U' F' U F";
+
+/// Inserts the edge at the front of the top layer into the front left slot.
+const LEFT_INSERT: &str = "U' L' U L U F U' F'";
+
+/// Flips two yellow edges, turning a dot into an L, an L into a line and a line into the cross.
+const YELLOW_CROSS: &str = "F R U R' U' F'";
+
+/// Swaps the yellow edges at the front and left, without flipping any of them.
+const SWAP_EDGES: &str = "R U R' U R U2 R' U";
+
+/// Cycles three yellow corners and keeps the one at the front right, without moving any edges.
+const CYCLE_CORNERS: &str = "U R U' L' U R' U' L";
+
+/// Twists the corner at the front right, when repeated twice or four times. It scrambles the lower layers
+/// on the way, but six repetitions cancel out.
+const TWIST_CORNER: &str = "R' D' R D";
+
+/// The faces of [`Cube`] in the order of the face turns of [`FACE_TURNS`].
+const FACES: [usize; 6] = [
+    Cube::TOP,
+    Cube::RIGHT,
+    Cube::FRONT,
+    Cube::BOTTOM,
+    Cube::LEFT,
+    Cube::BACK,
+];
+
+/// The stages of the beginner's method, in order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// The four white edges, lined up with the centers next to them.
+    WhiteCross,
+    /// The four white corners, which completes the first layer.
+    WhiteCorners,
+    /// The four edges of the middle layer, which completes the first two layers.
+    MiddleEdges,
+    /// The four yellow edges, first flipped to show a cross and then lined up with their centers.
+    YellowCross,
+    /// The four yellow corners, first moved to their places and then twisted, which solves the cube.
+    YellowCorners,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Stage::WhiteCross => "white cross",
+            Stage::WhiteCorners => "white corners",
+            Stage::MiddleEdges => "middle edges",
+            Stage::YellowCross => "yellow cross",
+            Stage::YellowCorners => "yellow corners",
+        })
+    }
+}
+
+/// One step of a [`BeginnerSolution`], which usually solves a single piece.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub stage: Stage,
+    pub moves: Vec<Move>,
+    /// Which piece the step is about, where it is and why the moves solve it, in plain words.
+    pub explanation: String,
+}
+
+impl Step {
+    /// The number of moves in this step.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+impl Display for Step {
+    /// Writes the moves on the first line and the explanation, indented, on the second.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} ({} moves)\n    {}",
+            self.stage,
+            MoveSequence(self.moves.clone()),
+            self.len(),
+            self.explanation
+        )
+    }
+}
+
+/// A solution found by a [`BeginnerSolver`], one step at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeginnerSolution {
+    /// The rotation that turns the white face to the bottom, before any of the steps. It is empty if the
+    /// white face is already on the bottom.
+    pub rotation: Vec<Move>,
+    /// Every step of every stage, in order. Each stage has at least one step, which has no moves if an
+    /// earlier stage happened to solve it.
+    pub steps: Vec<Step>,
+}
+
+impl BeginnerSolution {
+    /// The steps of one stage, in order.
+    pub fn stage(&self, stage: Stage) -> impl Iterator<Item = &Step> {
+        self.steps.iter().filter(move |step| step.stage == stage)
+    }
+
+    /// The whole solution as one sequence, starting with the rotation.
+    pub fn moves(&self) -> Vec<Move> {
+        let steps = self
+            .steps
+            .iter()
+            .flat_map(|step| step.moves.iter().copied());
+        self.rotation.iter().copied().chain(steps).collect()
+    }
+
+    /// The number of moves of all steps, without the rotation.
+    pub fn len(&self) -> usize {
+        self.steps.iter().map(Step::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Display for BeginnerSolution {
+    /// Writes the rotation and every step on their own lines.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.rotation.is_empty() {
+            writeln!(f, "rotation: {}", MoveSequence(self.rotation.clone()))?;
+        }
+        for step in &self.steps {
+            writeln!(f, "{step}")?;
+        }
+        write!(f, "total: {} moves", self.len())
+    }
+}
+
+/// A solver that uses the beginner's method, for solutions that explain themselves.
+///
+/// - The white edges are placed one at a time with the fewest moves that keep the ones already placed,
+///   with the help of tables of their distances.
+/// - All later pieces are solved with a handful of algorithms. For every piece, the solver tries each
+///   algorithm from every side of the cube, after turning the top layer, and keeps the shortest that
+///   solves the piece without breaking what is already solved. Pieces are solved in the order that takes
+///   the fewest moves next.
+///
+/// The algorithms are always written for the slot at the front right and then turned to the side of the
+/// slot they are used for, so the solutions never rotate the cube after the first rotation.
+///
+/// ```
+/// use cubers::{beginner::BeginnerSolver, Cube, MoveSequence, Turnable};
+///
+/// let mut cube = Cube::default();
+/// cube.perform_all(&"R U R' U' F2 D L'".parse::<MoveSequence>()?.0);
+///
+/// let solution = BeginnerSolver::new().solve(cube)?;
+/// cube.perform_all(&solution.moves());
+/// assert!(cube.is_solved());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct BeginnerSolver {
+    edge_moves: PieceMoves,
+    /// The distances of the first one, two, three and four white edges of [`CROSS`] to their places, see
+    /// [`distances`].
+    cross: [Vec<u8>; 4],
+    /// How each face turn relates to a face turn from the side of each slot, see [`frames`].
+    frames: [[usize; MOVE_COUNT]; 4],
+}
+
+impl BeginnerSolver {
+    /// Builds the tables of the solver, which takes a few milliseconds.
+    pub fn new() -> Self {
+        let edge_moves = piece_moves(2, |turn| (&turn.ep, &turn.eo));
+        let cross = std::array::from_fn(|n| {
+            let pieces: Vec<_> = CROSS[..=n]
+                .iter()
+                .map(|&edge| (&edge_moves, edge * 2))
+                .collect();
+            distances(&pieces)
+        });
+        Self {
+            edge_moves,
+            cross,
+            frames: frames().map(|(_, relabeled)| relabeled),
+        }
+    }
+
+    /// Solves `cube` with the white face on the bottom, or returns an error if it can't be solved at all.
+    pub fn solve(&self, mut cube: Cube) -> Result<BeginnerSolution, ValidationError> {
+        cube.validate()?;
+        let rotation = cube.rotation_to_bottom(Color::White);
+        cube.perform_all(&rotation);
+
+        let mut solve = Solve {
+            solver: self,
+            cubies: CubieCube::relative_to_centers(&cube)?,
+            centers: std::array::from_fn(|face| cube.face(face).get(4)),
+            steps: Vec::new(),
+        };
+        solve.white_cross();
+        solve.white_corners();
+        solve.middle_edges();
+        solve.yellow_cross();
+        solve.yellow_corners();
+        debug_assert_eq!(solve.cubies, CubieCube::SOLVED);
+
+        Ok(BeginnerSolution {
+            rotation,
+            steps: solve.steps,
+        })
+    }
+
+    /// The face turns of `algorithm`, turned to the side of `slot`.
+    fn algorithm(&self, algorithm: &str, slot: usize) -> Vec<usize> {
+        turns(algorithm)
+            .into_iter()
+            .map(|m| self.frames[slot][m])
+            .collect()
+    }
+
+    /// The center that faces the front when `slot` is held at the front right.
+    fn front(&self, slot: usize) -> usize {
+        FACES[self.frames[slot][6] / 3]
+    }
+}
+
+impl Default for BeginnerSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The state of one solve, with the white face on the bottom.
+struct Solve<'a> {
+    solver: &'a BeginnerSolver,
+    cubies: CubieCube,
+    /// The colors of the centers, in the order of [`Cube`].
+    centers: [Color; 6],
+    steps: Vec<Step>,
+}
+
+impl Solve<'_> {
+    fn white_cross(&mut self) {
+        let bottom = self.color(Cube::BOTTOM);
+        for (n, &edge) in CROSS.iter().enumerate() {
+            let table = &self.solver.cross[n];
+            let mut codes: Vec<_> = CROSS[..=n]
+                .iter()
+                .map(|&edge| edge_code(&self.cubies, edge))
+                .collect();
+            let mut moves = Vec::new();
+            let mut distance = table[index(&codes)];
+            while distance > 0 {
+                let (m, next) = (0..MOVE_COUNT)
+                    .map(|m| {
+                        let next: Vec<_> = codes
+                            .iter()
+                            .map(|&code| self.solver.edge_moves[code as usize][m])
+                            .collect();
+                        (m, next)
+                    })
+                    .find(|(_, next)| table[index(next)] == distance - 1)
+                    .unwrap();
+                moves.push(m);
+                codes = next;
+                distance -= 1;
+            }
+
+            let name = self.edge_name(edge);
+            let target = self.place(&EDGE_FACELETS[edge as usize]);
+            let explanation = if moves.is_empty() {
+                format!("The {name} is already {target} with its {bottom} sticker on the bottom.")
+            } else {
+                let position = self.edge_position(edge);
+                let flipped = if position == edge as usize {
+                    ", but flipped"
+                } else {
+                    ""
+                };
+                let kept = if n > 0 {
+                    ", without moving the edges that are already placed"
+                } else {
+                    ""
+                };
+                format!(
+                    "The {name} is {}{flipped}. These moves bring it {target} with its {bottom} \
+                     sticker on the bottom{kept}.",
+                    self.place(&EDGE_FACELETS[position]),
+                )
+            };
+            self.step(Stage::WhiteCross, moves, explanation);
+        }
+    }
+
+    fn white_corners(&mut self) {
+        let (solver, bottom) = (self.solver, self.color(Cube::BOTTOM));
+        let mut remaining = vec![0, 1, 2, 3];
+        while !remaining.is_empty() {
+            let kept = Kept::first_layer(&self.cubies);
+            let (slot, (moves, (out, repeats))) = remaining
+                .iter()
+                .map(|&slot| {
+                    let corner = 4 + slot as u8;
+                    let position = self.corner_position(corner);
+                    // a corner in the wrong place or twisted in the bottom layer has to come out first
+                    let out = (position >= 4 && !corner_solved(&self.cubies, corner))
+                        .then(|| position - 4);
+                    let options = (0..4).flat_map(move |auf| {
+                        [1, 3, 5].map(|repeats| {
+                            let mut moves = Vec::new();
+                            if let Some(out) = out {
+                                extend(&mut moves, solver.algorithm(CORNER_OUT, out));
+                            }
+                            extend(&mut moves, auf_turns(auf));
+                            for _ in 0..repeats {
+                                extend(&mut moves, solver.algorithm(CORNER_INSERT, slot));
+                            }
+                            (moves, (out, repeats))
+                        })
+                    });
+                    let solved =
+                        |cubies: &CubieCube| corner_solved(cubies, corner) && kept.holds(cubies);
+                    (
+                        slot,
+                        self.shortest(corner_solved(&self.cubies, corner), options, solved),
+                    )
+                })
+                .min_by_key(|(_, (moves, _))| moves.len())
+                .unwrap();
+            remaining.retain(|&other| other != slot);
+
+            let corner = 4 + slot as u8;
+            let name = self.corner_name(corner);
+            let target = self.place(&CORNER_FACELETS[corner as usize]);
+            let explanation = if moves.is_empty() {
+                format!("The {name} is already {target} with its {bottom} sticker on the bottom.")
+            } else {
+                let position = self.corner_position(corner);
+                let mut explanation =
+                    format!("The {name} is {}.", self.place(&CORNER_FACELETS[position]));
+                if out.is_some() {
+                    explanation += &format!(
+                        " It is stuck in the bottom layer, so {CORNER_OUT} from its side lifts it \
+                         into the top layer."
+                    );
+                }
+                explanation += &format!(
+                    " Turning the top layer puts it right above its place {target}, and {} {} \
+                     with the {} center in front twists it in with its {bottom} sticker on the \
+                     bottom, without touching the rest of the first layer.",
+                    CORNER_INSERT,
+                    times(repeats),
+                    self.color(self.solver.front(slot)),
+                );
+                explanation
+            };
+            self.step(Stage::WhiteCorners, moves, explanation);
+        }
+    }
+
+    fn middle_edges(&mut self) {
+        let (solver, top) = (self.solver, self.color(Cube::TOP));
+        let mut remaining = vec![0, 1, 2, 3];
+        while !remaining.is_empty() {
+            let kept = Kept::middle_layer(&self.cubies);
+            let (slot, (moves, (out, insert))) = remaining
+                .iter()
+                .map(|&slot| {
+                    let edge = 8 + slot as u8;
+                    let position = self.edge_position(edge);
+                    // an edge in the wrong slot or flipped in its slot has to come out first
+                    let out =
+                        (position >= 8 && !edge_solved(&self.cubies, edge)).then(|| position - 8);
+                    let options = (0..4).flat_map(move |auf| {
+                        [RIGHT_INSERT, LEFT_INSERT]
+                            .into_iter()
+                            .flat_map(move |algorithm| {
+                                (0..4).map(move |side| {
+                                    let mut moves = Vec::new();
+                                    if let Some(out) = out {
+                                        extend(&mut moves, solver.algorithm(RIGHT_INSERT, out));
+                                    }
+                                    extend(&mut moves, auf_turns(auf));
+                                    extend(&mut moves, solver.algorithm(algorithm, side));
+                                    (moves, (out, (algorithm, side)))
+                                })
+                            })
+                    });
+                    let solved =
+                        |cubies: &CubieCube| edge_solved(cubies, edge) && kept.holds(cubies);
+                    (
+                        slot,
+                        self.shortest(edge_solved(&self.cubies, edge), options, solved),
+                    )
+                })
+                .min_by_key(|(_, (moves, _))| moves.len())
+                .unwrap();
+            remaining.retain(|&other| other != slot);
+
+            let edge = 8 + slot as u8;
+            let name = self.edge_name(edge);
+            let target = self.place(&EDGE_FACELETS[edge as usize]);
+            let explanation = if moves.is_empty() {
+                format!("The {name} is already {target} the right way round.")
+            } else {
+                let position = self.edge_position(edge);
+                let mut explanation =
+                    format!("The {name} is {}.", self.place(&EDGE_FACELETS[position]));
+                if let Some(out) = out {
+                    let problem = if out == slot {
+                        "flipped"
+                    } else {
+                        "in the wrong slot"
+                    };
+                    explanation += &format!(
+                        " It is {problem}, so {RIGHT_INSERT} from its side swaps it with an edge \
+                         of the top layer, which has no {top} sticker."
+                    );
+                }
+                let (algorithm, side) = insert;
+                let hand = if algorithm == RIGHT_INSERT {
+                    "right"
+                } else {
+                    "left"
+                };
+                explanation += &format!(
+                    " Turning the top layer lines up its side sticker with the {} center, and \
+                     the {hand} algorithm {algorithm} moves it down {target}, without breaking \
+                     the first layer.",
+                    self.color(self.solver.front(side)),
+                );
+                explanation
+            };
+            self.step(Stage::MiddleEdges, moves, explanation);
+        }
+    }
+
+    fn yellow_cross(&mut self) {
+        let top = self.color(Cube::TOP);
+        let kept = Kept::middle_layer(&self.cubies);
+        let mut flipped = false;
+        while shape(&self.cubies) != Shape::Cross {
+            let before = shape(&self.cubies);
+            let (auf, moves) = (0..4)
+                .map(|auf| {
+                    let mut moves = auf_turns(auf);
+                    extend(&mut moves, turns(YELLOW_CROSS));
+                    (auf, moves)
+                })
+                .min_by_key(|(_, moves)| {
+                    let mut cubies = self.cubies;
+                    apply(&mut cubies, moves);
+                    (std::cmp::Reverse(shape(&cubies)), moves.len())
+                })
+                .unwrap();
+            let mut after = self.cubies;
+            apply(&mut after, &moves);
+            debug_assert!(shape(&after) > before && kept.holds(&after));
+
+            let turn = if auf == 0 {
+                ""
+            } else {
+                "After turning the top layer to hold it the right way, "
+            };
+            let explanation = format!(
+                "The {top} stickers of the edges on top form {}. {turn}{YELLOW_CROSS} flips two \
+                 of the edges, which turns it into {}, without breaking the first two layers.",
+                before.name(),
+                shape(&after).name(),
+            );
+            self.step(Stage::YellowCross, moves, explanation);
+            flipped = true;
+        }
+        if !flipped {
+            self.step(
+                Stage::YellowCross,
+                Vec::new(),
+                format!("The {top} stickers of the edges on top already form a cross."),
+            );
+        }
+
+        // the edges only need to be in the right order around the top layer, that is, up to a turn of it
+        let in_order = |cubies: &CubieCube| {
+            (0..4).any(|auf| {
+                let mut cubies = *cubies;
+                apply(&mut cubies, &auf_turns(auf));
+                cubies.ep[..4] == [0, 1, 2, 3]
+            })
+        };
+        let mut swaps: Vec<usize> = Vec::new();
+        'search: for depth in 0..=3 {
+            for mut code in 0..4usize.pow(depth) {
+                let mut cubies = self.cubies;
+                let mut sequence = Vec::new();
+                for _ in 0..depth {
+                    sequence.push(code % 4);
+                    apply(&mut cubies, &auf_turns(code % 4));
+                    apply(&mut cubies, &turns(SWAP_EDGES));
+                    code /= 4;
+                }
+                if in_order(&cubies) {
+                    swaps = sequence;
+                    break 'search;
+                }
+            }
+        }
+        for auf in swaps {
+            let mut cubies = self.cubies;
+            apply(&mut cubies, &auf_turns(auf));
+            let mut after = cubies;
+            apply(&mut after, &turns(SWAP_EDGES));
+            // the algorithm ends with a turn of the top layer, so the edges it swaps are the ones that
+            // are out of place after the best turn back
+            let swapped = (0..4)
+                .map(|back| {
+                    let mut turned = after;
+                    apply(&mut turned, &auf_turns(back));
+                    (0..4)
+                        .filter(|&position| turned.ep[position] != cubies.ep[position])
+                        .map(|position| self.edge_name(cubies.ep[position]))
+                        .collect::<Vec<_>>()
+                })
+                .min_by_key(Vec::len)
+                .unwrap();
+            let turn = if auf == 0 {
+                ""
+            } else {
+                "After turning the top layer to hold them at the front and left, "
+            };
+            let explanation = format!(
+                "The {top} edges are not in the same order around the top layer as their centers. \
+                 {turn}{SWAP_EDGES} swaps the {} and the {}, and keeps the {top} cross and the \
+                 first two layers.",
+                swapped[0], swapped[1],
+            );
+            let mut moves = auf_turns(auf);
+            extend(&mut moves, turns(SWAP_EDGES));
+            self.step(Stage::YellowCross, moves, explanation);
+        }
+
+        let auf = self.top_auf(|cubies| cubies.ep[..4] == [0, 1, 2, 3]);
+        let explanation = if auf == 0 {
+            format!("The {top} edges line up with their centers.")
+        } else {
+            format!("The {top} edges are in order, so turning the top layer lines them up with their centers.")
+        };
+        self.step(Stage::YellowCross, auf_turns(auf), explanation);
+    }
+
+    fn yellow_corners(&mut self) {
+        let top = self.color(Cube::TOP);
+        let placed = |cubies: &CubieCube| cubies.cp[..4] == [0, 1, 2, 3];
+        let mut cycles: Vec<usize> = Vec::new();
+        'search: for depth in 0..=3 {
+            for mut code in 0..4usize.pow(depth) {
+                let mut cubies = self.cubies;
+                let mut sequence = Vec::new();
+                for _ in 0..depth {
+                    sequence.push(code % 4);
+                    apply(&mut cubies, &self.solver.algorithm(CYCLE_CORNERS, code % 4));
+                    code /= 4;
+                }
+                if placed(&cubies) {
+                    cycles = sequence;
+                    break 'search;
+                }
+            }
+        }
+        if cycles.is_empty() {
+            self.step(
+                Stage::YellowCorners,
+                Vec::new(),
+                format!(
+                    "Every {top} corner is already in its place, if not twisted the right way."
+                ),
+            );
+        }
+        for side in cycles {
+            let moves = self.solver.algorithm(CYCLE_CORNERS, side);
+            let mut cycled = CubieCube::SOLVED;
+            apply(&mut cycled, &moves);
+            let kept = (0..4)
+                .find(|&position| cycled.cp[position] == position as u8)
+                .unwrap();
+            let front = self.color(self.solver.front(side));
+            let mut after = self.cubies;
+            apply(&mut after, &moves);
+            let explanation = if self.cubies.cp[kept] == kept as u8 {
+                let result = if placed(&after) {
+                    "into their places"
+                } else {
+                    "one step around, so that doing it again takes them the rest of the way"
+                };
+                format!(
+                    "The {} is in its place {}, if not twisted the right way. Holding it at the \
+                     front right with the {front} center in front, {CYCLE_CORNERS} cycles the other \
+                     three {top} corners {result}, without moving any edges.",
+                    self.corner_name(kept as u8),
+                    self.place(&CORNER_FACELETS[kept]),
+                )
+            } else {
+                format!(
+                    "None of the {top} corners is in its place yet. With the {front} center in \
+                     front, {CYCLE_CORNERS} cycles three of them, without moving any edges, to bring \
+                     one home."
+                )
+            };
+            self.step(Stage::YellowCorners, moves, explanation);
+        }
+
+        let mut twisted = false;
+        while let Some(position) = (0..4).find(|&position| self.cubies.co[position] != 0) {
+            // the top layer turns the twisted corner to the front right, where the algorithm twists it
+            let auf = (0..4)
+                .find(|&auf| {
+                    let mut turned = CubieCube::SOLVED;
+                    apply(&mut turned, &auf_turns(auf));
+                    turned.cp[0] == position as u8
+                })
+                .unwrap();
+            let mut moves = auf_turns(auf);
+            let mut cubies = self.cubies;
+            apply(&mut cubies, &moves);
+            let mut repeats = 0;
+            // the corner is only back at the front right after an even number of repetitions
+            while cubies.co[0] != 0 {
+                for _ in 0..2 {
+                    apply(&mut cubies, &turns(TWIST_CORNER));
+                    extend(&mut moves, turns(TWIST_CORNER));
+                }
+                repeats += 2;
+            }
+            let corner = self.cubies.cp[position];
+            let turn = if auf == 0 {
+                "It is at the front right, so "
+            } else {
+                "Turning the top layer brings it to the front right, and then "
+            };
+            let explanation = format!(
+                "The {} has its {top} sticker on the side. {turn}{TWIST_CORNER} {} turns its \
+                 {top} sticker up. The first two layers look scrambled until every corner is \
+                 twisted, but come back at the end.",
+                self.corner_name(corner),
+                times(repeats),
+            );
+            self.step(Stage::YellowCorners, moves, explanation);
+            twisted = true;
+        }
+        let auf = self.top_auf(|cubies| *cubies == CubieCube::SOLVED);
+        let explanation = match (twisted, auf) {
+            (_, 0) => {
+                format!("Every {top} corner is twisted the right way, so the cube is solved.")
+            }
+            (true, _) => format!(
+                "Turning the top layer back lines the {top} face up with the rest of the cube, \
+                 which solves it."
+            ),
+            (false, _) => format!(
+                "Turning the top layer lines the {top} face up with the rest of the cube, which \
+                 solves it."
+            ),
+        };
+        self.step(Stage::YellowCorners, auf_turns(auf), explanation);
+    }
+
+    /// The turn of the top layer, see [`auf_turns`], after which `solved` holds.
+    fn top_auf(&self, solved: impl Fn(&CubieCube) -> bool) -> usize {
+        (0..4)
+            .find(|&auf| {
+                let mut cubies = self.cubies;
+                apply(&mut cubies, &auf_turns(auf));
+                solved(&cubies)
+            })
+            .unwrap()
+    }
+
+    /// The shortest of `options` (moves along with what they do) after which `solved` holds, or no moves
+    /// at all if it already holds.
+    fn shortest<T: Default>(
+        &self,
+        already: bool,
+        options: impl Iterator<Item = (Vec<usize>, T)>,
+        solved: impl Fn(&CubieCube) -> bool,
+    ) -> (Vec<usize>, T) {
+        if already {
+            return (Vec::new(), T::default());
+        }
+        options
+            .filter(|(moves, _)| {
+                let mut cubies = self.cubies;
+                apply(&mut cubies, moves);
+                solved(&cubies)
+            })
+            .min_by_key(|(moves, _)| moves.len())
+            .expect("one of the algorithms solves the piece")
+    }
+
+    fn step(&mut self, stage: Stage, moves: Vec<usize>, explanation: String) {
+        apply(&mut self.cubies, &moves);
+        self.steps.push(Step {
+            stage,
+            moves: moves.into_iter().map(to_move).collect(),
+            explanation,
+        });
+    }
+
+    fn color(&self, face: usize) -> &'static str {
+        name(self.centers[face])
+    }
+
+    /// Where the stickers `facelets` are, by the centers next to them, like "between the yellow and red
+    /// centers".
+    fn place(&self, facelets: &[(usize, usize)]) -> String {
+        let colors: Vec<_> = facelets.iter().map(|&(face, _)| self.color(face)).collect();
+        match &colors[..] {
+            [a, b] => format!("between the {a} and {b} centers"),
+            [a, b, c] => format!("between the {a}, {b} and {c} centers"),
+            _ => unreachable!("pieces have two or three stickers"),
+        }
+    }
+
+    /// The name of an edge by its colors, like "white-red edge".
+    fn edge_name(&self, edge: u8) -> String {
+        let [(a, _), (b, _)] = EDGE_FACELETS[edge as usize];
+        format!("{}-{} edge", self.color(a), self.color(b))
+    }
+
+    /// The name of a corner by its colors, like "white-green-red corner".
+    fn corner_name(&self, corner: u8) -> String {
+        let [(a, _), (b, _), (c, _)] = CORNER_FACELETS[corner as usize];
+        format!(
+            "{}-{}-{} corner",
+            self.color(a),
+            self.color(b),
+            self.color(c)
+        )
+    }
+
+    fn edge_position(&self, edge: u8) -> usize {
+        self.cubies.ep.iter().position(|&e| e == edge).unwrap()
+    }
+
+    fn corner_position(&self, corner: u8) -> usize {
+        self.cubies.cp.iter().position(|&c| c == corner).unwrap()
+    }
+}
+
+/// The pieces that a step must not break, which are the ones that are already solved.
+struct Kept {
+    corners: Vec<u8>,
+    edges: Vec<u8>,
+}
+
+impl Kept {
+    /// The white cross and the white corners that are solved.
+    fn first_layer(cubies: &CubieCube) -> Self {
+        Self {
+            corners: (4..8).filter(|&c| corner_solved(cubies, c)).collect(),
+            edges: CROSS.to_vec(),
+        }
+    }
+
+    /// The first layer and the middle edges that are solved.
+    fn middle_layer(cubies: &CubieCube) -> Self {
+        let mut edges = CROSS.to_vec();
+        edges.extend((8..12).filter(|&e| edge_solved(cubies, e)));
+        Self {
+            corners: vec![4, 5, 6, 7],
+            edges,
+        }
+    }
+
+    fn holds(&self, cubies: &CubieCube) -> bool {
+        self.corners.iter().all(|&c| corner_solved(cubies, c))
+            && self.edges.iter().all(|&e| edge_solved(cubies, e))
+    }
+}
+
+/// What the top stickers of the edges of the top layer look like, from the furthest from a cross to the
+/// cross itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Shape {
+    Dot,
+    L,
+    Line,
+    Cross,
+}
+
+impl Shape {
+    fn name(self) -> &'static str {
+        match self {
+            Shape::Dot => "a dot",
+            Shape::L => "an L",
+            Shape::Line => "a line",
+            Shape::Cross => "a cross",
+        }
+    }
+}
+
+fn shape(cubies: &CubieCube) -> Shape {
+    let flipped = &cubies.eo[..4];
+    match flipped.iter().filter(|&&o| o == 0).count() {
+        0 => Shape::Dot,
+        2 if flipped[0] == flipped[2] => Shape::Line,
+        2 => Shape::L,
+        4 => Shape::Cross,
+        _ => unreachable!("an even number of edges is flipped"),
+    }
+}
+
+fn corner_solved(cubies: &CubieCube, corner: u8) -> bool {
+    cubies.cp[corner as usize] == corner && cubies.co[corner as usize] == 0
+}
+
+fn edge_solved(cubies: &CubieCube, edge: u8) -> bool {
+    cubies.ep[edge as usize] == edge && cubies.eo[edge as usize] == 0
+}
+
+/// The face turns of `algorithm`, indexed like in [`FACE_TURNS`].
+fn turns(algorithm: &str) -> Vec<usize> {
+    parse(algorithm)
+        .into_iter()
+        .map(|m| (0..MOVE_COUNT).find(|&t| to_move(t) == m).unwrap())
+        .collect()
+}
+
+/// The face turns of a turn of the top layer: none, U, U2 or U'.
+fn auf_turns(auf: usize) -> Vec<usize> {
+    [vec![], vec![0], vec![1], vec![2]][auf].clone()
+}
+
+/// Appends `turns` to `moves`, merging turns of the same face so that the steps read naturally.
+fn extend(moves: &mut Vec<usize>, turns: Vec<usize>) {
+    for m in turns {
+        match moves.last() {
+            Some(&last) if last / 3 == m / 3 => {
+                moves.pop();
+                let quarters = (last % 3 + m % 3 + 2) % 4;
+                if quarters != 0 {
+                    moves.push(m / 3 * 3 + quarters - 1);
+                }
+            }
+            _ => moves.push(m),
+        }
+    }
+}
+
+fn apply(cubies: &mut CubieCube, moves: &[usize]) {
+    for &m in moves {
+        *cubies = cubies.multiply(&FACE_TURNS[m]);
+    }
+}
+
+fn times(repeats: usize) -> String {
+    match repeats {
+        1 => "once".to_string(),
+        2 => "twice".to_string(),
+        n => format!("{n} times"),
+    }
+}
+
+fn name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Yellow => "yellow",
+        Color::Green => "green",
+        Color::Blue => "blue",
+        Color::Orange => "orange",
+        Color::Red => "red",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn solver() -> &'static BeginnerSolver {
+        static SOLVER: OnceLock<BeginnerSolver> = OnceLock::new();
+        SOLVER.get_or_init(BeginnerSolver::new)
+    }
+
+    /// Random scrambles along with their solutions, and the pieces right after each stage of them.
+    fn solves() -> Vec<(BeginnerSolution, [CubieCube; 5])> {
+        let mut rng = StdRng::seed_from_u64(25);
+        (0..20)
+            .map(|_| {
+                let scramble: Vec<_> = (0..25)
+                    .map(|_| to_move(rng.random_range(0..MOVE_COUNT)))
+                    .collect();
+                let mut cube = Cube::default();
+                cube.perform_all(&scramble);
+
+                let solution = solver().solve(cube).unwrap();
+                cube.perform_all(&solution.rotation);
+                let mut cubies = CubieCube::relative_to_centers(&cube).unwrap();
+                let stages = [
+                    Stage::WhiteCross,
+                    Stage::WhiteCorners,
+                    Stage::MiddleEdges,
+                    Stage::YellowCross,
+                    Stage::YellowCorners,
+                ]
+                .map(|stage| {
+                    for step in solution.stage(stage) {
+                        cubies.perform_all(&step.moves);
+                    }
+                    cubies
+                });
+                (solution, stages)
+            })
+            .collect()
+    }
+
+    fn first_layer(cubies: &CubieCube) -> bool {
+        CROSS.iter().all(|&edge| edge_solved(cubies, edge))
+            && (4..8).all(|corner| corner_solved(cubies, corner))
+    }
+
+    fn first_two_layers(cubies: &CubieCube) -> bool {
+        first_layer(cubies) && (8..12).all(|edge| edge_solved(cubies, edge))
+    }
+
+    #[test]
+    fn algorithms() {
+        let algorithm = |algorithm| {
+            let mut cubies = CubieCube::SOLVED;
+            apply(&mut cubies, &turns(algorithm));
+            cubies
+        };
+
+        // the edge at the front of the top layer keeps its front sticker on the front
+        let inserted = algorithm(RIGHT_INSERT);
+        assert!(first_layer(&inserted) && inserted.ep[8] == 1 && inserted.eo[8] == 1);
+        let inserted = algorithm(LEFT_INSERT);
+        assert!(first_layer(&inserted) && inserted.ep[9] == 1 && inserted.eo[9] == 1);
+        assert!(first_two_layers(&algorithm(YELLOW_CROSS)));
+
+        let swapped = algorithm(SWAP_EDGES);
+        assert!(first_two_layers(&swapped));
+        assert_eq!(swapped.ep[..4], [0, 2, 1, 3]);
+        assert_eq!(swapped.eo, [0; 12]);
+
+        let cycled = algorithm(CYCLE_CORNERS);
+        assert!(first_two_layers(&cycled));
+        assert_eq!(cycled.ep, CubieCube::SOLVED.ep);
+        assert_eq!(cycled.eo, [0; 12]);
+        assert_eq!(cycled.cp[0], 0);
+
+        let mut twisted = algorithm(TWIST_CORNER);
+        apply(&mut twisted, &turns(TWIST_CORNER));
+        assert_eq!(twisted.co[0], 2);
+        assert!((0..4).all(|edge| edge_solved(&twisted, edge)));
+        for _ in 0..4 {
+            apply(&mut twisted, &turns(TWIST_CORNER));
+        }
+        assert_eq!(twisted, CubieCube::SOLVED);
+    }
+
+    #[test]
+    fn white_cross() {
+        for (solution, [cubies, ..]) in solves() {
+            assert!(CROSS.iter().all(|&edge| edge_solved(&cubies, edge)));
+            let steps: Vec<_> = solution.stage(Stage::WhiteCross).collect();
+            assert_eq!(steps.len(), 4);
+            for step in steps {
+                assert!(step.explanation.starts_with("The white-"));
+                assert!(step.explanation.contains(" edge is "));
+            }
+        }
+    }
+
+    #[test]
+    fn white_corners() {
+        for (solution, [_, cubies, ..]) in solves() {
+            assert!(first_layer(&cubies));
+            let steps: Vec<_> = solution.stage(Stage::WhiteCorners).collect();
+            assert_eq!(steps.len(), 4);
+            for step in steps {
+                assert!(step.explanation.starts_with("The white-"));
+                assert!(step.explanation.contains(" corner is "));
+            }
+        }
+    }
+
+    #[test]
+    fn middle_edges() {
+        for (solution, [_, _, cubies, ..]) in solves() {
+            assert!(first_two_layers(&cubies));
+            let steps: Vec<_> = solution.stage(Stage::MiddleEdges).collect();
+            assert_eq!(steps.len(), 4);
+            for step in steps {
+                assert!(step.explanation.contains(" edge is "));
+                assert!(
+                    !step.explanation.contains("white-") && !step.explanation.contains("yellow-")
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn yellow_cross() {
+        for (solution, [_, _, _, cubies, _]) in solves() {
+            assert!(first_two_layers(&cubies));
+            assert!((0..4).all(|edge| edge_solved(&cubies, edge)));
+            let steps: Vec<_> = solution.stage(Stage::YellowCross).collect();
+            assert!(steps.iter().all(|step| step.explanation.contains("yellow")));
+        }
+    }
+
+    #[test]
+    fn yellow_corners() {
+        for (solution, [.., cubies]) in solves() {
+            assert_eq!(cubies, CubieCube::SOLVED);
+            let steps: Vec<_> = solution.stage(Stage::YellowCorners).collect();
+            assert!(steps.iter().all(|step| step.explanation.contains("yellow")));
+        }
+    }
+
+    #[test]
+    fn solved() {
+        let solution = solver().solve(Cube::default()).unwrap();
+        assert_eq!(solution.rotation, [Move::X2]);
+        assert!(solution.is_empty());
+        assert_eq!(solution.steps.len(), 4 + 4 + 4 + 2 + 2);
+    }
+
+    #[test]
+    fn display() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[Move::X2, Move::R, Move::U, Move::RPrime, Move::UPrime]);
+        cube.perform_all(&[Move::X2]);
+
+        let solution = solver().solve(cube).unwrap();
+        let text = solution.to_string();
+        assert!(text.starts_with(
+            "rotation: x2\nwhite cross:  (0 moves)\n    The white-red edge is already"
+        ));
+        assert!(text.ends_with(&format!("total: {} moves", solution.len())));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut cubies = CubieCube::SOLVED;
+        cubies.eo[0] = 1;
+        assert_eq!(
+            solver().solve(cubies.into()),
+            Err(ValidationError::EdgeFlip)
+        );
+    }
+}
@@ -0,0 +1,714 @@
+//! Solving 3x3 rubiks cubes the way speedcubers do, with the CFOP method.
+//!
+//! CFOP solves a cube in four stages: a cross on one face, the first two layers (F2L) one pair of a corner
+//! and an edge at a time, the orientation of the last layer (OLL) and finally its permutation (PLL). The
+//! solutions are much longer than the ones of the other solvers, but they can be followed and learned from.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    cube::{Color, Cube, ValidationError},
+    cubie::{to_move, CubieCube, MOVE_COUNT},
+    method::{corner_code, distances, edge_code, frames, index, piece_moves, PieceMoves, CROSS},
+    notation::{parse, MoveSequence},
+    turn::{Move, Turnable},
+};
+
+/// An algorithm for each of the 57 OLL cases, by their usual numbers.
+const OLL: [(&str, &str); 57] = [
+    ("OLL 1", "R U2 R2 F R F' U2 R' F R F'"),
+    ("OLL 2", "F R U R' U' F' f R U R' U' f'"),
+    ("OLL 3", "f R U R' U' f' U' F R U R' U' F'"),
+    ("OLL 4", "f R U R' U' f' U F R U R' U' F'"),
+    ("OLL 5", "r' U2 R U R' U r"),
+    ("OLL 6", "r U2 R' U' R U' r'"),
+    ("OLL 7", "r U R' U R U2 r'"),
+    ("OLL 8", "l' U' L U' L' U2 l"),
+    ("OLL 9", "R U R' U' R' F R2 U R' U' F'"),
+    ("OLL 10", "R U R' U R' F R F' R U2 R'"),
+    ("OLL 11", "M R U R' U R U2 R' U M'"),
+    ("OLL 12", "M' R' U' R U' R' U2 R U' M"),
+    ("OLL 13", "F U R U' R2 F' R U R U' R'"),
+    ("OLL 14", "R' F R U R' F' R F U' F'"),
+    ("OLL 15", "r' U' r R' U' R U r' U r"),
+    ("OLL 16", "r U r' R U R' U' r U' r'"),
+    ("OLL 17", "R U R' U R' F R F' U2 R' F R F'"),
+    ("OLL 18", "r U R' U R U2 r2 U' R U' R' U2 r"),
+    ("OLL 19", "M U R U R' U' M' R' F R F'"),
+    ("OLL 20", "r U R' U' M2 U R U' R' U' M'"),
+    ("OLL 21", "R U2 R' U' R U R' U' R U' R'"),
+    ("OLL 22", "R U2 R2 U' R2 U' R2 U2 R"),
+    ("OLL 23", "R2 D' R U2 R' D R U2 R"),
+    ("OLL 24", "r U R' U' r' F R F'"),
+    ("OLL 25", "F' r U R' U' r' F R"),
+    ("OLL 26", "R U2 R' U' R U' R'"),
+    ("OLL 27", "R U R' U R U2 R'"),
+    ("OLL 28", "r U R' U' r' R U R U' R'"),
+    ("OLL 29", "R U R' U' R U' R' F' U' F R U R'"),
+    ("OLL 30", "F U R U2 R' U' R U2 R' U' F'"),
+    ("OLL 31", "R' U' F U R U' R' F' R"),
+    ("OLL 32", "L U F' U' L' U L F L'"),
+    ("OLL 33", "R U R' U' R' F R F'"),
+    ("OLL 34", "R U R2 U' R' F R U R U' F'"),
+    ("OLL 35", "R U2 R2 F R F' R U2 R'"),
+    ("OLL 36", "L' U' L U' L' U L U L F' L' F"),
+    ("OLL 37", "F R' F' R U R U' R'"),
+    ("OLL 38", "R U R' U R U' R' U' R' F R F'"),
+    ("OLL 39", "L F' L' U' L U F U' L'"),
+    ("OLL 40", "R' F R U R' U' F' U R"),
+    ("OLL 41", "R U R' U R U2 R' F R U R' U' F'"),
+    ("OLL 42", "R' U' R U' R' U2 R F R U R' U' F'"),
+    ("OLL 43", "R' U' F' U F R"),
+    ("OLL 44", "F U R U' R' F'"),
+    ("OLL 45", "F R U R' U' F'"),
+    ("OLL 46", "R' U' R' F R F' U R"),
+    ("OLL 47", "F' L' U' L U L' U' L U F"),
+    ("OLL 48", "F R U R' U' R U R' U' F'"),
+    ("OLL 49", "r U' r2 U r2 U r2 U' r"),
+    ("OLL 50", "r' U r2 U' r2 U' r2 U r'"),
+    ("OLL 51", "F U R U' R' U R U' R' F'"),
+    ("OLL 52", "R' F' U' F U' R U R' U R"),
+    ("OLL 53", "r' U' R U' R' U R U' R' U2 r"),
+    ("OLL 54", "r U R' U R U' R' U R U2 r'"),
+    ("OLL 55", "R U2 R2 U' R U' R' U2 F R F'"),
+    ("OLL 56", "F R U R' U' R F' r U R' U' r'"),
+    ("OLL 57", "R U R' U' M' U R U' r'"),
+];
+
+/// An algorithm for each of the 21 PLL cases.
+const PLL: [(&str, &str); 21] = [
+    ("Aa perm", "R' F R' B2 R F' R' B2 R2"),
+    ("Ab perm", "R2 B2 R F R' B2 R F' R"),
+    ("E perm", "x' R U' R' D R U R' D' R U R' D R U' R' D' x"),
+    ("F perm", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    ("Ga perm", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    ("Gb perm", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    ("Gc perm", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    ("Gd perm", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    ("H perm", "M2 U M2 U2 M2 U M2"),
+    ("Ja perm", "R' U L' U2 R U' R' U2 R L"),
+    ("Jb perm", "R U R' F' R U R' U' R' F R2 U' R'"),
+    (
+        "Na perm",
+        "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'",
+    ),
+    ("Nb perm", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    ("Ra perm", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("Rb perm", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    ("T perm", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("Ua perm", "M2 U M U2 M' U M2"),
+    ("Ub perm", "M2 U' M U2 M' U' M2"),
+    ("V perm", "R U' R U R' D R D' R U' D R2 U R2 D' R2"),
+    ("Y perm", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("Z perm", "M' U M2 U M2 U M' U2 M2"),
+];
+
+/// The turns of the top layer that line up the last layer with an algorithm, or with the rest of the cube.
+const AUF: [&[Move]; 4] = [&[], &[Move::U], &[Move::U2], &[Move::UPrime]];
+
+/// The F2L slots, each made up of a bottom corner and the middle edge next to it. In the order of the
+/// corners DFR, DLF, DBL and DRB, and the edges FR, FL, BL and BR.
+const SLOTS: [&str; 4] = [
+    "front right pair",
+    "front left pair",
+    "back left pair",
+    "back right pair",
+];
+
+/// F2L pairs are solved without turning the bottom face, like most people do.
+const D_FACE: usize = 3;
+
+/// One stage of a [`CfopSolution`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// What the step solves, like "cross", "front right pair", "OLL 27" or "T perm". Steps of the last
+    /// layer that are already solved are called "OLL skip" or "PLL skip".
+    pub name: &'static str,
+    pub moves: Vec<Move>,
+}
+
+impl Step {
+    /// The number of moves in this step.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} ({} moves)",
+            self.name,
+            MoveSequence(self.moves.clone()),
+            self.len()
+        )
+    }
+}
+
+/// A solution found by a [`CfopSolver`], one step at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CfopSolution {
+    /// The rotation that turns the face with the cross color to the bottom, before any of the steps. It is
+    /// empty if the cross color is already on the bottom.
+    pub rotation: Vec<Move>,
+    pub cross: Step,
+    /// The four pairs, in the order they are solved. Pairs that an earlier step happened to solve have no
+    /// moves.
+    pub f2l: Vec<Step>,
+    pub oll: Step,
+    pub pll: Step,
+}
+
+impl CfopSolution {
+    /// All steps, in order.
+    pub fn steps(&self) -> impl Iterator<Item = &Step> {
+        std::iter::once(&self.cross)
+            .chain(&self.f2l)
+            .chain([&self.oll, &self.pll])
+    }
+
+    /// The whole solution as one sequence, starting with the rotation.
+    pub fn moves(&self) -> Vec<Move> {
+        let steps = self.steps().flat_map(|step| step.moves.iter().copied());
+        self.rotation.iter().copied().chain(steps).collect()
+    }
+
+    /// The number of moves of all steps, without the rotation.
+    pub fn len(&self) -> usize {
+        self.steps().map(Step::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Display for CfopSolution {
+    /// Writes the rotation and every step on its own line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.rotation.is_empty() {
+            writeln!(f, "rotation: {}", MoveSequence(self.rotation.clone()))?;
+        }
+        for step in self.steps() {
+            writeln!(f, "{step}")?;
+        }
+        write!(f, "total: {} moves", self.len())
+    }
+}
+
+/// A solver that uses the CFOP method, for solutions that people can follow.
+///
+/// - The cross is solved optimally, with the help of a table of the distances of every cross.
+/// - The F2L pairs are solved one at a time with the shortest sequence that keeps the cross and the pairs
+///   that are already solved, always picking the pair that takes the fewest moves next. This search is
+///   guided by tables of the distances of the cross along with the corner or the edge of a pair.
+/// - The cases of the last layer are recognized and solved with a table of well known algorithms, after
+///   lining them up with a turn of the top layer.
+///
+/// Building the tables takes about a second, so a solver should be reused.
+///
+/// ```
+/// use cubers::{cfop::CfopSolver, Color, Cube, MoveSequence, Turnable};
+///
+/// let mut cube = Cube::default();
+/// cube.perform_all(&"R U R' U' F2 D L'".parse::<MoveSequence>()?.0);
+///
+/// let solution = CfopSolver::new().solve(cube, Color::Yellow)?;
+/// cube.perform_all(&solution.moves());
+/// assert!(cube.is_solved());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct CfopSolver {
+    corner_moves: PieceMoves,
+    edge_moves: PieceMoves,
+    /// The distances of the cross edges to the solved cross, see [`distances`].
+    cross: Vec<u8>,
+    /// The distances of the cross edges and the corner of the front right pair to their solved places.
+    cross_corner: Vec<u8>,
+    /// The distances of the cross edges and the edge of the front right pair to their solved places.
+    cross_edge: Vec<u8>,
+    /// See [`frames`].
+    frames: [(CubieCube, [usize; MOVE_COUNT]); 4],
+    /// The turn of the top layer and the algorithm that orient the last layer, by the orientations of the
+    /// corners and edges of the top layer.
+    oll: HashMap<[u8; 8], (usize, usize)>,
+    /// The turns of the top layer before and after the algorithm that permute the last layer, by the
+    /// pieces in the corners and edges of the top layer.
+    pll: HashMap<[u8; 8], (usize, usize, usize)>,
+}
+
+impl CfopSolver {
+    /// Builds the tables of the solver and recognizes the cases of the algorithms.
+    pub fn new() -> Self {
+        let corner_moves = piece_moves(3, |turn| (&turn.cp, &turn.co));
+        let edge_moves = piece_moves(2, |turn| (&turn.ep, &turn.eo));
+        let cross_pieces = CROSS.map(|edge| (&edge_moves, edge * 2));
+        let cross = distances(&cross_pieces);
+        let [a, b, c, d] = cross_pieces;
+        let cross_corner = distances(&[a, b, c, d, (&corner_moves, 4 * 3)]);
+        let cross_edge = distances(&[a, b, c, d, (&edge_moves, 8 * 2)]);
+
+        let frames = frames();
+        let mut oll = HashMap::new();
+        for (i, (_, algorithm)) in OLL.iter().enumerate() {
+            for (auf, moves) in AUF.iter().enumerate() {
+                let case = undone(&[moves, &parse(algorithm)[..]]);
+                oll.entry(oll_key(&case)).or_insert((auf, i));
+            }
+        }
+        let mut pll = HashMap::new();
+        for (i, (_, algorithm)) in PLL.iter().enumerate() {
+            for (before, first) in AUF.iter().enumerate() {
+                for (after, last) in AUF.iter().enumerate() {
+                    let case = undone(&[first, &parse(algorithm)[..], last]);
+                    pll.entry(pll_key(&case)).or_insert((before, i, after));
+                }
+            }
+        }
+
+        Self {
+            corner_moves,
+            edge_moves,
+            cross,
+            cross_corner,
+            cross_edge,
+            frames,
+            oll,
+            pll,
+        }
+    }
+
+    /// Solves `cube` with the cross on the face with the `cross` color, or returns an error if it can't be
+    /// solved at all.
+    pub fn solve(&self, mut cube: Cube, cross: Color) -> Result<CfopSolution, ValidationError> {
+        cube.validate()?;
+        let rotation = cube.rotation_to_bottom(cross);
+        cube.perform_all(&rotation);
+        let mut cubies = CubieCube::relative_to_centers(&cube)?;
+
+        let cross = self.solve_cross(&cubies);
+        cubies.perform_all(&cross.moves);
+        let mut f2l = Vec::new();
+        let mut remaining = vec![0, 1, 2, 3];
+        while !remaining.is_empty() {
+            let (slot, step) = self.solve_pair(&cubies, &remaining);
+            remaining.retain(|&other| other != slot);
+            cubies.perform_all(&step.moves);
+            f2l.push(step);
+        }
+        let oll = self.solve_oll(&cubies);
+        cubies.perform_all(&oll.moves);
+        let pll = self.solve_pll(&cubies);
+        cubies.perform_all(&pll.moves);
+        debug_assert_eq!(cubies, CubieCube::SOLVED);
+
+        Ok(CfopSolution {
+            rotation,
+            cross,
+            f2l,
+            oll,
+            pll,
+        })
+    }
+
+    /// An optimal cross, found by always turning closer to the solved cross.
+    fn solve_cross(&self, cubies: &CubieCube) -> Step {
+        let mut codes = CROSS.map(|edge| edge_code(cubies, edge));
+        let mut moves = Vec::new();
+        while self.cross[index(&codes)] > 0 {
+            let distance = self.cross[index(&codes)];
+            let (m, next) = (0..MOVE_COUNT)
+                .map(|m| (m, codes.map(|code| self.edge_moves[code as usize][m])))
+                .find(|(_, next)| self.cross[index(next)] < distance)
+                .unwrap();
+            moves.push(to_move(m));
+            codes = next;
+        }
+        Step {
+            name: "cross",
+            moves,
+        }
+    }
+
+    /// Solves the one of the `remaining` pairs that takes the fewest moves, without breaking the cross or
+    /// any solved pair. Pairs that are already solved take no moves, so they come first.
+    fn solve_pair(&self, cubies: &CubieCube, remaining: &[usize]) -> (usize, Step) {
+        let searches: Vec<_> = remaining
+            .iter()
+            .map(|&slot| {
+                let (rotation, _) = &self.frames[slot];
+                let rotated = rotation.inverse().multiply(cubies).multiply(rotation);
+                let search = PairSearch {
+                    solver: self,
+                    kept: [1, 2, 3].map(|slot| pair_solved(&rotated, slot)),
+                    path: Vec::new(),
+                };
+                (slot, search, Pair::new(&rotated))
+            })
+            .collect();
+
+        for depth in 0.. {
+            for (slot, search, pair) in &searches {
+                let mut search = search.clone();
+                if search.search(*pair, depth, MOVE_COUNT) {
+                    let (_, relabeled) = &self.frames[*slot];
+                    let moves = search.path.iter().map(|&m| to_move(relabeled[m]));
+                    let step = Step {
+                        name: SLOTS[*slot],
+                        moves: moves.collect(),
+                    };
+                    return (*slot, step);
+                }
+            }
+        }
+        unreachable!("every pair can be solved")
+    }
+
+    fn solve_oll(&self, cubies: &CubieCube) -> Step {
+        match self.oll.get(&oll_key(cubies)) {
+            None => Step {
+                name: "OLL skip",
+                moves: Vec::new(),
+            },
+            Some(&(auf, i)) => {
+                let (name, algorithm) = OLL[i];
+                let mut moves = AUF[auf].to_vec();
+                moves.extend(parse(algorithm));
+                Step { name, moves }
+            }
+        }
+    }
+
+    fn solve_pll(&self, cubies: &CubieCube) -> Step {
+        match self.pll.get(&pll_key(cubies)) {
+            None => {
+                // only the top layer is turned
+                let auf = (0..4)
+                    .find(|&auf| {
+                        let mut cubies = *cubies;
+                        cubies.perform_all(AUF[auf]);
+                        cubies == CubieCube::SOLVED
+                    })
+                    .unwrap();
+                Step {
+                    name: "PLL skip",
+                    moves: AUF[auf].to_vec(),
+                }
+            }
+            Some(&(before, i, after)) => {
+                let (name, algorithm) = PLL[i];
+                let mut moves = AUF[before].to_vec();
+                moves.extend(parse(algorithm));
+                moves.extend(AUF[after]);
+                Step { name, moves }
+            }
+        }
+    }
+}
+
+impl Default for CfopSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The codes of the pieces that matter for solving the front right pair.
+#[derive(Copy, Clone, Debug)]
+struct Pair {
+    cross: [u8; 4],
+    corner: u8,
+    edge: u8,
+    /// The corners of the other pairs, DLF, DBL and DRB.
+    corners: [u8; 3],
+    /// The edges of the other pairs, FL, BL and BR.
+    edges: [u8; 3],
+}
+
+impl Pair {
+    fn new(cubies: &CubieCube) -> Self {
+        Self {
+            cross: CROSS.map(|edge| edge_code(cubies, edge)),
+            corner: corner_code(cubies, 4),
+            edge: edge_code(cubies, 8),
+            corners: [5, 6, 7].map(|corner| corner_code(cubies, corner)),
+            edges: [9, 10, 11].map(|edge| edge_code(cubies, edge)),
+        }
+    }
+}
+
+/// An iterative deepening search for a solution of the front right pair.
+#[derive(Clone)]
+struct PairSearch<'a> {
+    solver: &'a CfopSolver,
+    /// Whether each of the other pairs is solved and has to stay that way.
+    kept: [bool; 3],
+    path: Vec<usize>,
+}
+
+impl PairSearch<'_> {
+    /// Looks for a solution of exactly `depth` moves, after a move of the face `last`.
+    fn search(&mut self, pair: Pair, depth: u8, last: usize) -> bool {
+        let cross = index(&pair.cross) * 24;
+        let estimate = self.solver.cross_corner[cross + pair.corner as usize]
+            .max(self.solver.cross_edge[cross + pair.edge as usize]);
+        if estimate > depth {
+            return false;
+        }
+        if depth == 0 {
+            return (0..3).all(|i| {
+                !self.kept[i]
+                    || (pair.corners[i] == (5 + i as u8) * 3 && pair.edges[i] == (9 + i as u8) * 2)
+            });
+        }
+
+        let solver = self.solver;
+        for m in 0..MOVE_COUNT {
+            let face = m / 3;
+            // turns of opposite faces commute, so they are only tried in one order
+            if face == D_FACE || face == last || face + 3 == last {
+                continue;
+            }
+
+            let edge = |code: u8| solver.edge_moves[code as usize][m];
+            let corner = |code: u8| solver.corner_moves[code as usize][m];
+            let next = Pair {
+                cross: pair.cross.map(edge),
+                corner: corner(pair.corner),
+                edge: edge(pair.edge),
+                corners: pair.corners.map(corner),
+                edges: pair.edges.map(edge),
+            };
+            self.path.push(m);
+            if self.search(next, depth - 1, face) {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+}
+
+/// The cube that `sequences` solve, one after another.
+fn undone(sequences: &[&[Move]]) -> CubieCube {
+    let mut cubies = CubieCube::SOLVED;
+    for &m in sequences.iter().rev().flat_map(|moves| moves.iter().rev()) {
+        cubies.perform(m.inverse());
+    }
+    cubies
+}
+
+fn oll_key(cubies: &CubieCube) -> [u8; 8] {
+    std::array::from_fn(|i| {
+        if i < 4 {
+            cubies.co[i]
+        } else {
+            cubies.eo[i - 4]
+        }
+    })
+}
+
+fn pll_key(cubies: &CubieCube) -> [u8; 8] {
+    std::array::from_fn(|i| {
+        if i < 4 {
+            cubies.cp[i]
+        } else {
+            cubies.ep[i - 4]
+        }
+    })
+}
+
+/// Whether the corner and edge of a slot are solved, see [`SLOTS`].
+fn pair_solved(cubies: &CubieCube, slot: usize) -> bool {
+    let (corner, edge) = (4 + slot, 8 + slot);
+    cubies.cp[corner] == corner as u8
+        && cubies.co[corner] == 0
+        && cubies.ep[edge] == edge as u8
+        && cubies.eo[edge] == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn solver() -> &'static CfopSolver {
+        static SOLVER: OnceLock<CfopSolver> = OnceLock::new();
+        SOLVER.get_or_init(CfopSolver::new)
+    }
+
+    fn f2l_solved(cubies: &CubieCube) -> bool {
+        CROSS
+            .iter()
+            .all(|&edge| edge_code(cubies, edge) == edge * 2)
+            && (0..4).all(|slot| pair_solved(cubies, slot))
+    }
+
+    #[test]
+    fn algorithms() {
+        for (name, algorithm) in OLL {
+            let mut cubies = undone(&[&parse(algorithm)]);
+            assert!(f2l_solved(&cubies), "{name}");
+            assert_ne!(oll_key(&cubies), [0; 8], "{name}");
+            cubies.perform_all(&parse(algorithm));
+            assert_eq!(cubies, CubieCube::SOLVED, "{name}");
+        }
+        for (name, algorithm) in PLL {
+            let cubies = undone(&[&parse(algorithm)]);
+            assert!(f2l_solved(&cubies), "{name}");
+            assert_eq!(oll_key(&cubies), [0; 8], "{name}");
+        }
+
+        // every case but the solved one, in every orientation of the top layer
+        let solver = solver();
+        assert_eq!(solver.oll.len(), 27 * 8 - 1);
+        assert_eq!(solver.pll.len(), 24 * 24 / 2 - 4);
+        let mut cases: Vec<_> = solver.oll.values().map(|&(_, i)| i).collect();
+        cases.sort();
+        cases.dedup();
+        assert_eq!(cases.len(), OLL.len());
+        let mut cases: Vec<_> = solver.pll.values().map(|&(_, i, _)| i).collect();
+        cases.sort();
+        cases.dedup();
+        assert_eq!(cases.len(), PLL.len());
+    }
+
+    #[test]
+    fn cross_distances() {
+        let mut counts = [0; 9];
+        for &distance in solver().cross.iter().filter(|&&d| d != u8::MAX) {
+            counts[distance as usize] += 1;
+        }
+        assert_eq!(counts, [1, 15, 158, 1394, 9809, 46381, 97254, 34966, 102]);
+    }
+
+    #[test]
+    fn optimal_cross() {
+        let mut cube = Cube::default();
+        cube.perform_all(&parse("F2 L2 B"));
+
+        let solution = solver().solve(cube, Color::Yellow).unwrap();
+        assert!(solution.rotation.is_empty());
+        assert_eq!(solution.cross.len(), 3);
+    }
+
+    #[test]
+    fn cross_color() {
+        let mut cube = Cube::default();
+        cube.perform_all(&parse("R U F' L2 D B"));
+        for color in [
+            Color::White,
+            Color::Yellow,
+            Color::Orange,
+            Color::Red,
+            Color::Green,
+            Color::Blue,
+        ] {
+            let solution = solver().solve(cube, color).unwrap();
+            let mut rotated = cube;
+            rotated.perform_all(&solution.rotation);
+            assert_eq!(rotated.face(Cube::BOTTOM).get(4), color);
+            rotated.perform_all(&solution.cross.moves);
+            let bottom = rotated.face(Cube::BOTTOM);
+            assert!([1, 3, 5, 7].iter().all(|&i| bottom.get(i) == color));
+        }
+    }
+
+    #[test]
+    fn random_scrambles() {
+        let mut rng = StdRng::seed_from_u64(24);
+        for _ in 0..10 {
+            let scramble: Vec<_> = (0..25)
+                .map(|_| to_move(rng.random_range(0..MOVE_COUNT)))
+                .collect();
+            let mut cube = Cube::default();
+            cube.perform_all(&scramble);
+
+            let solution = solver().solve(cube, Color::White).unwrap();
+            let mut cubies = {
+                let mut rotated = cube;
+                rotated.perform_all(&solution.rotation);
+                CubieCube::relative_to_centers(&rotated).unwrap()
+            };
+            cubies.perform_all(&solution.cross.moves);
+            assert!(CROSS
+                .iter()
+                .all(|&edge| edge_code(&cubies, edge) == edge * 2));
+
+            let mut slots: Vec<_> = solution.f2l.iter().map(|step| step.name).collect();
+            slots.sort();
+            assert_eq!(
+                slots,
+                [
+                    "back left pair",
+                    "back right pair",
+                    "front left pair",
+                    "front right pair"
+                ]
+            );
+            for (i, step) in solution.f2l.iter().enumerate() {
+                cubies.perform_all(&step.moves);
+                let solved = (0..4).filter(|&slot| pair_solved(&cubies, slot)).count();
+                assert!(solved > i, "{step}");
+                assert!(step.len() <= 11, "{step}");
+            }
+            assert!(f2l_solved(&cubies));
+
+            cubies.perform_all(&solution.oll.moves);
+            assert_eq!(oll_key(&cubies), [0; 8]);
+            cubies.perform_all(&solution.pll.moves);
+            assert_eq!(cubies, CubieCube::SOLVED);
+
+            cube.perform_all(&solution.moves());
+            assert!(cube.is_solved());
+        }
+    }
+
+    #[test]
+    fn solved() {
+        let solution = solver().solve(Cube::default(), Color::Yellow).unwrap();
+        assert!(solution.is_empty());
+        assert_eq!(solution.oll.name, "OLL skip");
+        assert_eq!(solution.pll.name, "PLL skip");
+    }
+
+    #[test]
+    fn display() {
+        let mut cube = Cube::default();
+        cube.perform_all(&parse("R U R' U R U2 R'"));
+
+        let solution = solver().solve(cube, Color::Yellow).unwrap();
+        assert_eq!(
+            solution.to_string(),
+            "cross:  (0 moves)
+front right pair:  (0 moves)
+front left pair:  (0 moves)
+back left pair:  (0 moves)
+back right pair:  (0 moves)
+OLL 26: R U2 R' U' R U' R' (7 moves)
+PLL skip:  (0 moves)
+total: 7 moves"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let mut cubies = CubieCube::SOLVED;
+        cubies.co[0] = 1;
+        cubies.co[1] = 2;
+        cubies.co[2] = 0;
+        cubies.eo[0] = 1;
+        assert_eq!(
+            solver().solve(cubies.into(), Color::White),
+            Err(ValidationError::EdgeFlip)
+        );
+    }
+}
@@ -1,4 +1,13 @@
-use std::{fmt::Debug, hash::Hash};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+    str::FromStr,
+};
+
+use crate::{
+    cubie::{CubieCube, FaceletError},
+    turn::{Move, Turnable},
+};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -13,39 +22,30 @@ pub enum Color {
 }
 
 impl Color {
-    pub const BITS: usize = 5;
-
-    /// # Panic
-    /// This method panics if the u8 given as an argument does not represent
-    /// any valid color.
-    pub fn from_u8(n: u8) -> Self {
-        match n {
-            0b00000 => Self::White,
-            0b00001 => Self::Yellow,
-            0b00010 => Self::Green,
-            0b00100 => Self::Blue,
-            0b01000 => Self::Orange,
-            0b10000 => Self::Red,
-            n => panic!("unknown color: {n:0>8b}"),
-        }
-    }
+    pub(crate) const BITS: usize = 5;
 
-    pub unsafe fn from_u8_unchecked(n: u8) -> Self {
+    /// # Safety
+    /// `n` has to be the discriminant of one of the colors.
+    pub(crate) unsafe fn from_u8_unchecked(n: u8) -> Self {
         std::mem::transmute(n)
     }
 }
 
 /// This struct represents the colors one one face of a 3x3 rubiks cube.
 /// Each color is represented by a 5 bit number in this order:
+/// ```text
 ///     + --- +
 ///     | 012 |
 ///     | 345 |
 ///     | 678 |
 ///     + --- |
-
+/// ```
+///
 /// in this format:
+/// ```text
 ///     0bxxxxxxxxxxxxxxxxxxx888887777766666555554444433333222221111100000
-#[derive(Copy, Clone, PartialEq, Eq)]
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Face(u64);
 
 impl Face {
@@ -56,25 +56,60 @@ impl Face {
     pub const RED: Self = Face(0x108421084210);
     pub const ORANGE: Self = Face(0x84210842108);
 
+    /// Creates a face from its colors, in the index order shown above.
+    pub fn from_colors(colors: [Color; 9]) -> Self {
+        let mut face = Self::WHITE;
+        for (index, color) in colors.into_iter().enumerate() {
+            face.set(index, color);
+        }
+        face
+    }
+
+    /// The colors of this face, in the index order shown above.
+    pub fn colors(&self) -> [Color; 9] {
+        std::array::from_fn(|index| self.get(index))
+    }
+
+    /// # Panic
+    /// This method panics if `index` is not in `0..9`.
     pub fn get(&self, index: usize) -> Color {
+        assert!(index < 9, "face index out of range: {index}");
         let mask = 0x1F << (Color::BITS * index);
         let masked = (self.0 & mask) >> (Color::BITS * index);
         unsafe { Color::from_u8_unchecked(masked as u8) }
     }
 
+    /// # Panic
+    /// This method panics if `index` is not in `0..9`.
     pub fn set(&mut self, index: usize, color: Color) {
+        assert!(index < 9, "face index out of range: {index}");
         let mask = 0x1F << (Color::BITS * index);
         self.0 &= !mask;
         self.0 |= (color as u64) << (Color::BITS * index);
     }
 
-    pub fn copy_from_mask(&mut self, from: &Self, mask: u64) {
+    /// The face as stored, with the layout shown above.
+    pub(crate) fn to_bits(self) -> u64 {
+        self.0
+    }
+
+    /// The reverse of [`Face::to_bits`], or `None` if `bits` isn't a valid face.
+    pub(crate) fn from_bits(bits: u64) -> Option<Self> {
+        let valid = bits >> (Color::BITS * 9) == 0
+            && (0..9).all(|index| {
+                let color = (bits >> (Color::BITS * index)) & 0x1F;
+                color == 0 || color.is_power_of_two()
+            });
+        valid.then_some(Face(bits))
+    }
+
+    pub(crate) fn copy_from_mask(&mut self, from: &Self, mask: u64) {
         let masked = from.0 & mask;
         self.0 &= !mask;
         self.0 |= masked;
     }
 
-    pub fn copy_from_positions(&mut self, from_face: &Self, positions: &[(usize, usize)]) {
+    pub(crate) fn copy_from_positions(&mut self, from_face: &Self, positions: &[(usize, usize)]) {
         positions.iter().for_each(|&(from, to)| {
             let from_mask = 0x1f << (Color::BITS * from);
             let to_mask = 0x1f << (Color::BITS * to);
@@ -100,6 +135,19 @@ impl Face {
         self.set(1, three);
     }
 
+    /// Returns this face turned by 90° clockwise.
+    pub fn rotated_cw(mut self) -> Self {
+        self.cycle_edges_cw();
+        self
+    }
+
+    /// Returns this face turned by 180°.
+    pub fn rotated_half(mut self) -> Self {
+        self.cycle_edges_cw();
+        self.cycle_edges_cw();
+        self
+    }
+
     pub fn cycle_edges_ccw(&mut self) {
         let zero = self.get(0);
         let one = self.get(1);
@@ -131,6 +179,7 @@ impl Hash for Face {
 ///
 /// The indices of the faces are oriented like this:
 ///
+/// ```text
 ///             + --- +
 ///             | 012 |
 ///             | 345 |
@@ -144,9 +193,28 @@ impl Hash for Face {
 ///             | 345 |
 ///             | 678 |
 ///             + --- +
+/// ```
 ///
 /// Faces are stored in TOP BOTTOM LEFT RIGHT FRONT BACK order (white yellow orange red green blue)
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+///
+/// # Facelet strings
+///
+/// Cubes can be converted from and to the 54 character facelet strings used by Kociemba's solver and most
+/// other tools, like `UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB` for the solved cube. Each letter
+/// is the face a sticker belongs to in the default orientation, so `U` is white, `R` red, `F` green,
+/// `D` yellow, `L` orange and `B` blue. The faces come in U R F D L B order with nine stickers each, and
+/// the stickers of a face are in the same order as the indices above:
+///
+/// ```text
+/// U1..U9 -> top     0..=8     D1..D9 -> bottom  0..=8
+/// R1..R9 -> right   0..=8     L1..L9 -> left    0..=8
+/// F1..F9 -> front   0..=8     B1..B9 -> back    0..=8
+/// ```
+///
+/// That is, `U1` is the top left sticker of the top face when looking at it with the back face above it,
+/// `D1` is the top left sticker of the bottom face when looking at it with the front face above it, and
+/// the first sticker of every other face is its top left one when looking at it with the top face above.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Cube {
     data: [Face; 6],
 }
@@ -208,6 +276,217 @@ impl Cube {
     pub fn face_mut(&mut self, index: usize) -> &mut Face {
         &mut self.data[index]
     }
+
+    /// The size of [`Cube::to_bytes`].
+    pub(crate) const BYTES: usize = 6 * 8;
+
+    /// The faces as raw bytes, e.g. to store the cube in a file.
+    pub(crate) fn to_bytes(self) -> [u8; Self::BYTES] {
+        let mut bytes = [0; Self::BYTES];
+        for (chunk, face) in bytes.chunks_mut(8).zip(self.data) {
+            chunk.copy_from_slice(&face.to_bits().to_le_bytes());
+        }
+        bytes
+    }
+
+    /// The reverse of [`Cube::to_bytes`], or `None` if the bytes don't contain valid faces.
+    pub(crate) fn from_bytes(bytes: &[u8; Self::BYTES]) -> Option<Self> {
+        let mut cube = Cube::default();
+        for (face, chunk) in cube.data.iter_mut().zip(bytes.chunks(8)) {
+            *face = Face::from_bits(u64::from_le_bytes(chunk.try_into().unwrap()))?;
+        }
+        Some(cube)
+    }
+
+    /// Returns whether every face has a single color, no matter how the cube is oriented.
+    pub fn is_solved(&self) -> bool {
+        self.data
+            .iter()
+            .all(|face| (0..9).all(|i| face.get(i) == face.get(4)))
+    }
+
+    /// Checks that this cube could be the result of turning a solved cube, in any orientation.
+    ///
+    /// The solvers call this before they start searching, because an impossible state would make them
+    /// search until they run out of moves.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for color in [
+            Color::White,
+            Color::Yellow,
+            Color::Green,
+            Color::Blue,
+            Color::Orange,
+            Color::Red,
+        ] {
+            let count = self
+                .data
+                .iter()
+                .flat_map(|face| face.colors())
+                .filter(|&c| c == color)
+                .count();
+            if count != 9 {
+                return Err(ValidationError::ColorCount { color, count });
+            }
+        }
+
+        CubieCube::relative_to_centers(self)?.validate()
+    }
+
+    /// The rotation that turns the face with the `color` center to the bottom, without turning the cube
+    /// upside down if it is on a side.
+    pub(crate) fn rotation_to_bottom(&self, color: Color) -> Vec<Move> {
+        let face = (0..6).find(|&face| self.face(face).get(4) == color);
+        match face {
+            Some(Self::TOP) => vec![Move::X2],
+            Some(Self::LEFT) => vec![Move::ZPrime],
+            Some(Self::RIGHT) => vec![Move::Z],
+            Some(Self::FRONT) => vec![Move::XPrime],
+            Some(Self::BACK) => vec![Move::X],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns all 24 orientations of this cube.
+    pub fn orientations(&self) -> Vec<Cube> {
+        let mut orientations = vec![*self];
+        let mut i = 0;
+        while i < orientations.len() {
+            for rotation in [Move::X, Move::Y] {
+                let mut rotated = orientations[i];
+                rotated.perform(rotation);
+                if !orientations.contains(&rotated) {
+                    orientations.push(rotated);
+                }
+            }
+            i += 1;
+        }
+        orientations
+    }
+}
+
+/// The faces in the order they appear in a facelet string, with the letters they are written as.
+pub(crate) const FACELET_ORDER: [(usize, char); 6] = [
+    (Cube::TOP, 'U'),
+    (Cube::RIGHT, 'R'),
+    (Cube::FRONT, 'F'),
+    (Cube::BOTTOM, 'D'),
+    (Cube::LEFT, 'L'),
+    (Cube::BACK, 'B'),
+];
+
+impl Display for Cube {
+    /// Writes the facelet string of this cube, see [`Cube`] for the format.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let solved = Cube::default();
+        for (face, _) in FACELET_ORDER {
+            for color in self.face(face).colors() {
+                let (_, letter) = FACELET_ORDER
+                    .iter()
+                    .find(|&&(face, _)| solved.face(face).get(4) == color)
+                    .unwrap();
+                write!(f, "{letter}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cube {
+    type Err = FaceletStringError;
+
+    /// Reads a facelet string, see [`Cube`] for the format. The result isn't validated, so it may be a cube
+    /// that can't be solved.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let count = s.chars().count();
+        if count != 54 {
+            return Err(FaceletStringError::Length(count));
+        }
+
+        let solved = Cube::default();
+        let mut cube = Cube::default();
+        for (position, letter) in s.chars().enumerate() {
+            let (face, _) = FACELET_ORDER
+                .iter()
+                .find(|&&(_, l)| l == letter)
+                .ok_or(FaceletStringError::InvalidCharacter { position, letter })?;
+            let (target, _) = FACELET_ORDER[position / 9];
+            cube.face_mut(target)
+                .set(position % 9, solved.face(*face).get(4));
+        }
+        Ok(cube)
+    }
+}
+
+/// The reason a facelet string can't be read as a [`Cube`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FaceletStringError {
+    /// The string has this many characters instead of 54.
+    Length(usize),
+    /// A character other than `U`, `R`, `F`, `D`, `L` or `B`, at this (character) position.
+    InvalidCharacter { position: usize, letter: char },
+    /// The string has this many characters, which isn't the number of stickers of a 2x2 to 7x7 cube.
+    Size(usize),
+}
+
+impl Display for FaceletStringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaceletStringError::Length(count) => {
+                write!(f, "expected 54 facelets, got {count}")
+            }
+            FaceletStringError::InvalidCharacter { position, letter } => {
+                write!(f, "{letter:?} at position {position} is not a face")
+            }
+            FaceletStringError::Size(count) => {
+                write!(f, "{count} facelets don't make up a 2x2 to 7x7 cube")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FaceletStringError {}
+
+/// The reason a [`Cube`] can't be solved, as returned by [`Cube::validate`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// There are `count` stickers of `color` instead of 9.
+    ColorCount { color: Color, count: usize },
+    /// The stickers don't form a complete set of pieces.
+    Facelets(FaceletError),
+    /// A corner has been twisted in place.
+    CornerTwist,
+    /// An edge has been flipped in place.
+    EdgeFlip,
+    /// Two pieces have been swapped.
+    Parity,
+    /// The centers of a bigger cube have `count` stickers of `color`, instead of the same number of every
+    /// color.
+    CenterCount { color: Color, count: usize },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::ColorCount { color, count } => {
+                write!(f, "there are {count} {color:?} stickers instead of 9")
+            }
+            ValidationError::Facelets(error) => write!(f, "{error}"),
+            ValidationError::CornerTwist => write!(f, "a corner is twisted"),
+            ValidationError::EdgeFlip => write!(f, "an edge is flipped"),
+            ValidationError::Parity => write!(f, "two pieces are swapped"),
+            ValidationError::CenterCount { color, count } => {
+                write!(f, "there are {count} {color:?} center stickers")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<FaceletError> for ValidationError {
+    fn from(error: FaceletError) -> Self {
+        ValidationError::Facelets(error)
+    }
 }
 
 impl Default for Cube {
@@ -264,4 +543,94 @@ mod tests {
 
         assert_eq!(face, Face(Color::Yellow as u64));
     }
+
+    #[test]
+    fn colors_round_trip() {
+        use Color::*;
+
+        let colors = [White, Yellow, Green, Blue, Orange, Red, White, Green, Red];
+        let face = Face::from_colors(colors);
+
+        assert_eq!(face.colors(), colors);
+        assert_eq!(face.get(4), Orange);
+        assert_eq!(Face::from_colors([Blue; 9]), Face::BLUE);
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(Cube::default().validate(), Ok(()));
+
+        let mut cube = Cube::default();
+        cube.perform_all(&[Move::R, Move::U, Move::F2, Move::M, Move::Y]);
+        assert_eq!(cube.validate(), Ok(()));
+
+        let mut cube = Cube::default();
+        cube.top_mut().set(0, Color::Red);
+        assert_eq!(
+            cube.validate(),
+            Err(ValidationError::ColorCount {
+                color: Color::White,
+                count: 8
+            })
+        );
+
+        let mut cubies = CubieCube::SOLVED;
+        cubies.co[3] = 2;
+        assert_eq!(
+            Cube::from(cubies).validate(),
+            Err(ValidationError::CornerTwist)
+        );
+
+        let mut cubies = CubieCube::SOLVED;
+        cubies.eo[5] = 1;
+        assert_eq!(
+            Cube::from(cubies).validate(),
+            Err(ValidationError::EdgeFlip)
+        );
+
+        let mut cubies = CubieCube::SOLVED;
+        cubies.cp.swap(0, 1);
+        assert_eq!(Cube::from(cubies).validate(), Err(ValidationError::Parity));
+    }
+
+    #[test]
+    fn validate_duplicate_pieces() {
+        // two white-red-green corners, but the right number of stickers of every color
+        let mut cube = Cube::default();
+        cube.front_mut().set(0, Color::Red);
+        cube.left_mut().set(2, Color::Green);
+        cube.right_mut().set(1, Color::Orange);
+        assert_eq!(
+            cube.validate(),
+            Err(ValidationError::Facelets(FaceletError::DuplicateCorner(0)))
+        );
+    }
+
+    #[test]
+    fn facelet_string() {
+        const SOLVED: &str = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+        assert_eq!(Cube::default().to_string(), SOLVED);
+        assert_eq!(SOLVED.parse(), Ok(Cube::default()));
+
+        let mut cube = Cube::default();
+        cube.perform(Move::R);
+        let facelets = "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB";
+        assert_eq!(cube.to_string(), facelets);
+        assert_eq!(facelets.parse(), Ok(cube));
+
+        cube.perform_all(&[Move::U, Move::F2, Move::Lw, Move::S]);
+        assert_eq!(cube.to_string().parse(), Ok(cube));
+    }
+
+    #[test]
+    fn facelet_string_errors() {
+        assert_eq!("UUU".parse::<Cube>(), Err(FaceletStringError::Length(3)));
+        assert_eq!(
+            "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBW".parse::<Cube>(),
+            Err(FaceletStringError::InvalidCharacter {
+                position: 53,
+                letter: 'W'
+            })
+        );
+    }
 }
@@ -0,0 +1,802 @@
+use std::fmt::Display;
+
+use crate::{
+    cube::{Color, Cube, ValidationError},
+    turn::{Move, Turnable},
+};
+
+/// The number of face turns in the half turn metric: every face can be turned by 90°, 180° or 270°.
+pub(crate) const MOVE_COUNT: usize = 18;
+
+/// The facelets (face, index) of every corner position, starting with the facelet on the top or bottom face
+/// and continuing clockwise. Corners are numbered URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB.
+#[rustfmt::skip]
+pub(crate) const CORNER_FACELETS: [[(usize, usize); 3]; 8] = [
+    [(Cube::TOP, 8),    (Cube::RIGHT, 0), (Cube::FRONT, 2)],
+    [(Cube::TOP, 6),    (Cube::FRONT, 0), (Cube::LEFT, 2)],
+    [(Cube::TOP, 0),    (Cube::LEFT, 0),  (Cube::BACK, 2)],
+    [(Cube::TOP, 2),    (Cube::BACK, 0),  (Cube::RIGHT, 2)],
+    [(Cube::BOTTOM, 2), (Cube::FRONT, 8), (Cube::RIGHT, 6)],
+    [(Cube::BOTTOM, 0), (Cube::LEFT, 8),  (Cube::FRONT, 6)],
+    [(Cube::BOTTOM, 6), (Cube::BACK, 8),  (Cube::LEFT, 6)],
+    [(Cube::BOTTOM, 8), (Cube::RIGHT, 8), (Cube::BACK, 6)],
+];
+
+/// The facelets (face, index) of every edge position. The first facelet is the one that determines the
+/// orientation of the edge. Edges are numbered UR, UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR.
+#[rustfmt::skip]
+pub(crate) const EDGE_FACELETS: [[(usize, usize); 2]; 12] = [
+    [(Cube::TOP, 5),    (Cube::RIGHT, 1)],
+    [(Cube::TOP, 7),    (Cube::FRONT, 1)],
+    [(Cube::TOP, 3),    (Cube::LEFT, 1)],
+    [(Cube::TOP, 1),    (Cube::BACK, 1)],
+    [(Cube::BOTTOM, 5), (Cube::RIGHT, 7)],
+    [(Cube::BOTTOM, 1), (Cube::FRONT, 7)],
+    [(Cube::BOTTOM, 3), (Cube::LEFT, 7)],
+    [(Cube::BOTTOM, 7), (Cube::BACK, 7)],
+    [(Cube::FRONT, 5),  (Cube::RIGHT, 3)],
+    [(Cube::FRONT, 3),  (Cube::LEFT, 5)],
+    [(Cube::BACK, 5),   (Cube::LEFT, 3)],
+    [(Cube::BACK, 3),   (Cube::RIGHT, 5)],
+];
+
+/// This struct represents a 3x3 rubiks cube on the level of its pieces instead of its stickers.
+/// It is much smaller than [`Cube`] and the coordinates it provides can index lookup tables.
+///
+/// `cp[i]` is the corner that sits at corner position `i` and `co[i]` is its orientation (0, 1 or 2 clockwise twists).
+/// `ep[i]` and `eo[i]` are the same for edges, where the orientation is either 0 or 1 (flipped).
+///
+/// Pieces are placed relative to the centers and named after their colors, so a `CubieCube` doesn't
+/// know how the whole cube is rotated. Only cubes whose centers are colored like on [`Cube::default`]
+/// can be converted to it, and those come back unchanged.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CubieCube {
+    pub cp: [u8; 8],
+    pub co: [u8; 8],
+    pub ep: [u8; 12],
+    pub eo: [u8; 12],
+}
+
+impl CubieCube {
+    pub const SOLVED: Self = Self {
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        co: [0; 8],
+        ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        eo: [0; 12],
+    };
+
+    /// The clockwise quarter turns of the six faces, in U R F D L B order.
+    #[rustfmt::skip]
+    pub const MOVES: [Self; 6] = [
+        // U
+        Self {
+            cp: [3, 0, 1, 2, 4, 5, 6, 7],
+            co: [0, 0, 0, 0, 0, 0, 0, 0],
+            ep: [3, 0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11],
+            eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        },
+        // R
+        Self {
+            cp: [4, 1, 2, 0, 7, 5, 6, 3],
+            co: [2, 0, 0, 1, 1, 0, 0, 2],
+            ep: [8, 1, 2, 3, 11, 5, 6, 7, 4, 9, 10, 0],
+            eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        },
+        // F
+        Self {
+            cp: [1, 5, 2, 3, 0, 4, 6, 7],
+            co: [1, 2, 0, 0, 2, 1, 0, 0],
+            ep: [0, 9, 2, 3, 4, 8, 6, 7, 1, 5, 10, 11],
+            eo: [0, 1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0],
+        },
+        // D
+        Self {
+            cp: [0, 1, 2, 3, 5, 6, 7, 4],
+            co: [0, 0, 0, 0, 0, 0, 0, 0],
+            ep: [0, 1, 2, 3, 5, 6, 7, 4, 8, 9, 10, 11],
+            eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        },
+        // L
+        Self {
+            cp: [0, 2, 6, 3, 4, 1, 5, 7],
+            co: [0, 1, 2, 0, 0, 2, 1, 0],
+            ep: [0, 1, 10, 3, 4, 5, 9, 7, 8, 2, 6, 11],
+            eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        },
+        // B
+        Self {
+            cp: [0, 1, 3, 7, 4, 5, 2, 6],
+            co: [0, 0, 1, 2, 0, 0, 2, 1],
+            ep: [0, 1, 2, 11, 4, 5, 6, 10, 8, 9, 3, 7],
+            eo: [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1],
+        },
+    ];
+
+    /// The whole cube rotations x, y and z. Turning the whole cube doesn't change the state relative to the
+    /// centers, so these are only used to conjugate a state into the rotated frame.
+    #[rustfmt::skip]
+    pub(crate) const ROTATIONS: [Self; 3] = [
+        // x
+        Self {
+            cp: [4, 5, 1, 0, 7, 6, 2, 3],
+            co: [2, 1, 2, 1, 1, 2, 1, 2],
+            ep: [8, 5, 9, 1, 11, 7, 10, 3, 4, 6, 2, 0],
+            eo: [0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0],
+        },
+        // y
+        Self {
+            cp: [3, 0, 1, 2, 7, 4, 5, 6],
+            co: [0, 0, 0, 0, 0, 0, 0, 0],
+            ep: [3, 0, 1, 2, 7, 4, 5, 6, 11, 8, 9, 10],
+            eo: [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1],
+        },
+        // z
+        Self {
+            cp: [1, 5, 6, 2, 0, 4, 7, 3],
+            co: [1, 2, 1, 2, 2, 1, 2, 1],
+            ep: [2, 9, 6, 10, 0, 8, 4, 11, 1, 5, 7, 3],
+            eo: [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+        },
+    ];
+
+    /// Returns the cube that undoes `self`.
+    pub const fn inverse(&self) -> Self {
+        let mut result = Self::SOLVED;
+        let mut i = 0;
+        while i < 8 {
+            let to = self.cp[i] as usize;
+            result.cp[to] = i as u8;
+            result.co[to] = (3 - self.co[i]) % 3;
+            i += 1;
+        }
+        let mut i = 0;
+        while i < 12 {
+            let to = self.ep[i] as usize;
+            result.ep[to] = i as u8;
+            result.eo[to] = self.eo[i];
+            i += 1;
+        }
+        result
+    }
+
+    /// Returns the cube you get by first applying `self` and then `other`.
+    pub const fn multiply(&self, other: &Self) -> Self {
+        let mut result = Self::SOLVED;
+        let mut i = 0;
+        while i < 8 {
+            let from = other.cp[i] as usize;
+            result.cp[i] = self.cp[from];
+            result.co[i] = (self.co[from] + other.co[i]) % 3;
+            i += 1;
+        }
+        let mut i = 0;
+        while i < 12 {
+            let from = other.ep[i] as usize;
+            result.ep[i] = self.ep[from];
+            result.eo[i] = (self.eo[from] + other.eo[i]) % 2;
+            i += 1;
+        }
+        result
+    }
+
+    /// The corner orientation coordinate in `0..3^7`.
+    pub fn twist(&self) -> u16 {
+        self.co[..7].iter().fold(0, |acc, &o| acc * 3 + o as u16)
+    }
+
+    pub fn set_twist(&mut self, mut twist: u16) {
+        let mut sum = 0;
+        for i in (0..7).rev() {
+            self.co[i] = (twist % 3) as u8;
+            sum += self.co[i];
+            twist /= 3;
+        }
+        self.co[7] = (3 - sum % 3) % 3;
+    }
+
+    /// The corner permutation coordinate in `0..8!`.
+    pub fn corner_permutation(&self) -> u16 {
+        permutation_rank(&self.cp) as u16
+    }
+
+    pub fn set_corner_permutation(&mut self, rank: u16) {
+        permutation_unrank(rank as u32, &mut self.cp);
+    }
+
+    /// The edge orientation coordinate in `0..2^11`.
+    pub fn flip(&self) -> u16 {
+        self.eo[..11].iter().fold(0, |acc, &o| acc * 2 + o as u16)
+    }
+
+    pub fn set_flip(&mut self, mut flip: u16) {
+        let mut sum = 0;
+        for i in (0..11).rev() {
+            self.eo[i] = (flip % 2) as u8;
+            sum += self.eo[i];
+            flip /= 2;
+        }
+        self.eo[11] = sum % 2;
+    }
+
+    /// The positions and order of the four edges of the slice between the top and bottom face (FR, FL, BL, BR)
+    /// in `0..12*11*10*9`. Divided by 24 this is the set of positions they occupy, where 0 means they are
+    /// in their slice. The remainder is the order they are in, where 0 means they are in order.
+    pub fn slice_sorted(&self) -> u16 {
+        let mut combination = 0;
+        let mut order = [0; 4];
+        let mut found = 0;
+        for position in (0..12).rev() {
+            let edge = self.ep[position];
+            if edge >= 8 {
+                combination += binomial(11 - position, found + 1);
+                order[3 - found] = edge - 8;
+                found += 1;
+            }
+        }
+        combination * 24 + permutation_rank(&order) as u16
+    }
+
+    /// Places the slice edges according to `slice_sorted` and fills the other positions with the remaining
+    /// edges in order.
+    pub fn set_slice_sorted(&mut self, slice_sorted: u16) {
+        let mut order = [0; 4];
+        permutation_unrank(slice_sorted as u32 % 24, &mut order);
+
+        let mut combination = slice_sorted / 24;
+        let mut positions = [0; 4];
+        for k in (0..4).rev() {
+            let from_end = (k..12)
+                .take_while(|&n| binomial(n, k + 1) <= combination)
+                .last()
+                .unwrap_or(k);
+            combination -= binomial(from_end, k + 1);
+            positions[3 - k] = 11 - from_end;
+        }
+
+        let mut others = 0..8;
+        for position in 0..12 {
+            self.ep[position] = match positions.iter().position(|&p| p == position) {
+                Some(i) => 8 + order[i],
+                None => others.next().unwrap(),
+            };
+        }
+    }
+
+    /// The permutation of the eight edges of the top and bottom face in `0..8!`. This is only meaningful if
+    /// none of them are in the slice between those faces.
+    pub fn ud_edges(&self) -> u16 {
+        permutation_rank(&self.ep[..8]) as u16
+    }
+
+    pub fn set_ud_edges(&mut self, rank: u16) {
+        permutation_unrank(rank as u32, &mut self.ep[..8]);
+        self.ep[8..].copy_from_slice(&[8, 9, 10, 11]);
+    }
+}
+
+/// All 18 face turns, indexed by `face * 3 + quarter_turns - 1` with faces in U R F D L B order.
+pub(crate) const FACE_TURNS: [CubieCube; MOVE_COUNT] = {
+    let mut turns = [CubieCube::SOLVED; MOVE_COUNT];
+    let mut m = 0;
+    while m < MOVE_COUNT {
+        let turn = CubieCube::MOVES[m / 3];
+        turns[m] = if m % 3 == 0 {
+            turn
+        } else {
+            turns[m - 1].multiply(&turn)
+        };
+        m += 1;
+    }
+    turns
+};
+
+/// Converts a face turn indexed like in [`FACE_TURNS`] to a [`Move`].
+pub(crate) fn to_move(m: usize) -> Move {
+    use Move::*;
+
+    #[rustfmt::skip]
+    const MOVES: [Move; MOVE_COUNT] = [
+        U, U2, UPrime,
+        R, R2, RPrime,
+        F, F2, FPrime,
+        D, D2, DPrime,
+        L, L2, LPrime,
+        B, B2, BPrime
+    ];
+
+    MOVES[m]
+}
+
+pub(crate) fn binomial(n: usize, k: usize) -> u16 {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) as u16 / (i + 1) as u16)
+}
+
+/// The reason a [`Cube`] can't be read as a [`CubieCube`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FaceletError {
+    /// Two faces have a center of this color.
+    DuplicateCenter(Color),
+    /// The stickers at this corner position don't belong to any corner.
+    InvalidCorner(usize),
+    /// The stickers at this edge position don't belong to any edge.
+    InvalidEdge(usize),
+    /// This corner appears in more than one position.
+    DuplicateCorner(usize),
+    /// This edge appears in more than one position.
+    DuplicateEdge(usize),
+    /// The centers aren't colored like on [`Cube::default`], e.g. because the whole cube is rotated.
+    RotatedCenters,
+}
+
+impl Display for FaceletError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaceletError::DuplicateCenter(color) => write!(f, "more than one {color:?} center"),
+            FaceletError::InvalidCorner(position) => {
+                write!(
+                    f,
+                    "no corner has the stickers at corner position {position}"
+                )
+            }
+            FaceletError::InvalidEdge(position) => {
+                write!(f, "no edge has the stickers at edge position {position}")
+            }
+            FaceletError::DuplicateCorner(corner) => write!(f, "corner {corner} appears twice"),
+            FaceletError::DuplicateEdge(edge) => write!(f, "edge {edge} appears twice"),
+            FaceletError::RotatedCenters => {
+                write!(f, "the centers aren't in their default orientation")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FaceletError {}
+
+impl TryFrom<Cube> for CubieCube {
+    type Error = FaceletError;
+
+    /// Reads the pieces of a facelet cube, whose centers have to be colored like on [`Cube::default`].
+    fn try_from(cube: Cube) -> Result<Self, Self::Error> {
+        let cubies = Self::relative_to_centers(&cube)?;
+        let solved = Cube::default();
+        if (0..6).any(|face| cube.face(face).get(4) != solved.face(face).get(4)) {
+            return Err(FaceletError::RotatedCenters);
+        }
+        Ok(cubies)
+    }
+}
+
+impl CubieCube {
+    /// Reads the pieces of a facelet cube in any orientation. The colors of the center facelets decide
+    /// which face a sticker belongs to, so the pieces are the same as if the colors were renamed such
+    /// that every center has its default color, which is what [`Cube::from`] gives back.
+    ///
+    /// The solvers use this to solve a cube without rotating it first.
+    pub(crate) fn relative_to_centers(cube: &Cube) -> Result<Self, FaceletError> {
+        let centers: [Color; 6] = std::array::from_fn(|face| cube.face(face).get(4));
+        if let Some(&color) = centers
+            .iter()
+            .enumerate()
+            .find_map(|(i, color)| centers[..i].contains(color).then_some(color))
+        {
+            return Err(FaceletError::DuplicateCenter(color));
+        }
+        Self::read(cube, centers)
+    }
+
+    /// Reads the pieces of `cube`, where `centers` are the colors of the faces in the order of [`Cube`].
+    fn read(cube: &Cube, centers: [Color; 6]) -> Result<Self, FaceletError> {
+        let face_of = |(face, index): (usize, usize)| {
+            let color = cube.face(face).get(index);
+            centers.iter().position(|&center| center == color)
+        };
+
+        let mut result = Self::SOLVED;
+
+        for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let invalid = FaceletError::InvalidCorner(position);
+            let faces = facelets.map(face_of);
+            let orientation = faces
+                .iter()
+                .position(|&face| face == Some(Cube::TOP) || face == Some(Cube::BOTTOM))
+                .ok_or(invalid)?;
+            let clockwise = [0, 1, 2].map(|i| faces[(orientation + i) % 3]);
+            let corner = CORNER_FACELETS
+                .iter()
+                .position(|reference| reference.map(|(face, _)| Some(face)) == clockwise)
+                .ok_or(invalid)?;
+
+            if result.cp[..position].contains(&(corner as u8)) {
+                return Err(FaceletError::DuplicateCorner(corner));
+            }
+            result.cp[position] = corner as u8;
+            result.co[position] = orientation as u8;
+        }
+
+        for (position, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let faces = facelets.map(face_of);
+            let (edge, orientation) = EDGE_FACELETS
+                .iter()
+                .enumerate()
+                .find_map(|(edge, reference)| {
+                    let reference = reference.map(|(face, _)| Some(face));
+                    if reference == faces {
+                        Some((edge, 0))
+                    } else if reference == [faces[1], faces[0]] {
+                        Some((edge, 1))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(FaceletError::InvalidEdge(position))?;
+
+            if result.ep[..position].contains(&(edge as u8)) {
+                return Err(FaceletError::DuplicateEdge(edge));
+            }
+            result.ep[position] = edge as u8;
+            result.eo[position] = orientation;
+        }
+
+        Ok(result)
+    }
+
+    /// Checks that this cube can be solved by turning it, i.e. that its pieces haven't been twisted,
+    /// flipped or swapped by taking the cube apart.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for position in 0..8 {
+            let corner = self.cp[position];
+            if corner > 7 || self.co[position] > 2 {
+                return Err(FaceletError::InvalidCorner(position).into());
+            }
+            if self.cp[..position].contains(&corner) {
+                return Err(FaceletError::DuplicateCorner(corner as usize).into());
+            }
+        }
+        for position in 0..12 {
+            let edge = self.ep[position];
+            if edge > 11 || self.eo[position] > 1 {
+                return Err(FaceletError::InvalidEdge(position).into());
+            }
+            if self.ep[..position].contains(&edge) {
+                return Err(FaceletError::DuplicateEdge(edge as usize).into());
+            }
+        }
+
+        if !self.co.iter().sum::<u8>().is_multiple_of(3) {
+            return Err(ValidationError::CornerTwist);
+        }
+        if !self.eo.iter().sum::<u8>().is_multiple_of(2) {
+            return Err(ValidationError::EdgeFlip);
+        }
+        if odd_permutation(&self.cp) != odd_permutation(&self.ep) {
+            return Err(ValidationError::Parity);
+        }
+
+        Ok(())
+    }
+}
+
+impl From<CubieCube> for Cube {
+    /// Paints the stickers of every piece, with the centers colored like on [`Cube::default`].
+    fn from(cubies: CubieCube) -> Self {
+        let solved = Cube::default();
+        let color = |(face, index): (usize, usize)| solved.face(face).get(index);
+
+        let mut cube = solved;
+        for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let corner = CORNER_FACELETS[cubies.cp[position] as usize];
+            for (i, &(face, index)) in facelets.iter().enumerate() {
+                let from = (i + 3 - cubies.co[position] as usize) % 3;
+                cube.face_mut(face).set(index, color(corner[from]));
+            }
+        }
+        for (position, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let edge = EDGE_FACELETS[cubies.ep[position] as usize];
+            for (i, &(face, index)) in facelets.iter().enumerate() {
+                let from = (i + cubies.eo[position] as usize) % 2;
+                cube.face_mut(face).set(index, color(edge[from]));
+            }
+        }
+        cube
+    }
+}
+
+impl CubieCube {
+    fn turn(&mut self, m: usize) {
+        *self = self.multiply(&FACE_TURNS[m]);
+    }
+
+    fn rotate(&mut self, axis: usize) {
+        let rotation = &Self::ROTATIONS[axis];
+        *self = rotation.inverse().multiply(self).multiply(rotation);
+    }
+}
+
+impl Turnable for CubieCube {
+    fn r(&mut self) {
+        self.turn(3);
+    }
+
+    fn rprime(&mut self) {
+        self.turn(5);
+    }
+
+    fn l(&mut self) {
+        self.turn(12);
+    }
+
+    fn lprime(&mut self) {
+        self.turn(14);
+    }
+
+    fn u(&mut self) {
+        self.turn(0);
+    }
+
+    fn uprime(&mut self) {
+        self.turn(2);
+    }
+
+    fn d(&mut self) {
+        self.turn(9);
+    }
+
+    fn dprime(&mut self) {
+        self.turn(11);
+    }
+
+    fn f(&mut self) {
+        self.turn(6);
+    }
+
+    fn fprime(&mut self) {
+        self.turn(8);
+    }
+
+    fn b(&mut self) {
+        self.turn(15);
+    }
+
+    fn bprime(&mut self) {
+        self.turn(17);
+    }
+
+    fn x(&mut self) {
+        self.rotate(0);
+    }
+
+    fn y(&mut self) {
+        self.rotate(1);
+    }
+
+    fn z(&mut self) {
+        self.rotate(2);
+    }
+}
+
+/// Whether `perm` is made up of an odd number of swaps.
+pub(crate) fn odd_permutation(perm: &[u8]) -> bool {
+    let inversions = (0..perm.len())
+        .flat_map(|i| (i + 1..perm.len()).map(move |j| (i, j)))
+        .filter(|&(i, j)| perm[i] > perm[j])
+        .count();
+    inversions % 2 == 1
+}
+
+/// Ranks a permutation of `0..perm.len()` in lexicographical order.
+pub(crate) fn permutation_rank(perm: &[u8]) -> u32 {
+    let mut used = 0u32;
+    perm.iter().fold(0, |acc, &p| {
+        let smaller_unused = p as u32 - (used & ((1 << p) - 1)).count_ones();
+        used |= 1 << p;
+        acc * (perm.len() as u32 - used.count_ones() + 1) + smaller_unused
+    })
+}
+
+/// The inverse of [`permutation_rank`].
+pub(crate) fn permutation_unrank(mut rank: u32, perm: &mut [u8]) {
+    let n = perm.len();
+    let mut digits = [0u8; 12];
+    for i in (0..n).rev() {
+        let base = (n - i) as u32;
+        digits[i] = (rank % base) as u8;
+        rank /= base;
+    }
+
+    let mut used = 0u32;
+    for (i, &digit) in digits[..n].iter().enumerate() {
+        let value = (0..n as u8)
+            .filter(|&v| used & (1 << v) == 0)
+            .nth(digit as usize)
+            .unwrap();
+        used |= 1 << value;
+        perm[i] = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turn::{Move, Turnable};
+
+    #[test]
+    fn solved_from_facelets() {
+        assert_eq!(CubieCube::try_from(Cube::default()), Ok(CubieCube::SOLVED));
+        assert_eq!(Cube::from(CubieCube::SOLVED), Cube::default());
+    }
+
+    #[test]
+    fn moves_match_facelets() {
+        use Move::*;
+
+        for (i, turn) in [U, R, F, D, L, B].into_iter().enumerate() {
+            let mut cube = Cube::default();
+            cube.perform(turn);
+
+            assert_eq!(
+                CubieCube::try_from(cube),
+                Ok(CubieCube::MOVES[i]),
+                "{turn:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn turns_match_facelets() {
+        use Move::*;
+
+        let turns = [
+            R, U, RPrime, F, D, D, LPrime, B, U, F, BPrime, L, UPrime, DPrime, FPrime,
+        ];
+        let mut cube = Cube::default();
+        let mut cubies = CubieCube::SOLVED;
+        cube.perform_all(&turns);
+        cubies.perform_all(&turns);
+
+        assert_eq!(CubieCube::try_from(cube), Ok(cubies));
+        assert_eq!(Cube::from(cubies), cube);
+    }
+
+    #[test]
+    fn all_moves_match_facelets() {
+        use Move::*;
+
+        let turns = [
+            R2, M, U, EPrime, S2, Rw, X, F, Lw2, YPrime, Uw, B, SPrime, Dw, Z, M2, Fw, ZPrime,
+            BwPrime, E, L2, Y2, X2, Z2, S, MPrime, D2,
+        ];
+        let mut cube = Cube::default();
+        let mut cubies = CubieCube::SOLVED;
+        for turn in turns {
+            cube.perform(turn);
+            cubies.perform(turn);
+            assert_eq!(
+                CubieCube::relative_to_centers(&cube),
+                Ok(cubies),
+                "{turn:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn inverse() {
+        use Move::*;
+
+        let mut cubies = CubieCube::SOLVED;
+        cubies.perform_all(&[R, U, FPrime, D2, L, B]);
+        assert_eq!(cubies.multiply(&cubies.inverse()), CubieCube::SOLVED);
+        assert_eq!(cubies.inverse().multiply(&cubies), CubieCube::SOLVED);
+    }
+
+    #[test]
+    fn twisted_corner_round_trip() {
+        let mut cubies = CubieCube::SOLVED;
+        cubies.co[0] = 1;
+        cubies.eo[5] = 1;
+        cubies.ep.swap(2, 7);
+
+        assert_eq!(CubieCube::try_from(Cube::from(cubies)), Ok(cubies));
+    }
+
+    #[test]
+    fn round_trip() {
+        use Move::*;
+
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, FPrime, M, D2, MPrime, L, E2, B, E2]);
+        assert_eq!(Cube::from(CubieCube::try_from(cube).unwrap()), cube);
+
+        // slice moves turn the centers
+        cube.perform(M);
+        assert_eq!(CubieCube::try_from(cube), Err(FaceletError::RotatedCenters));
+    }
+
+    #[test]
+    fn relative_to_rotated_centers() {
+        use Move::*;
+
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, FPrime, X, D2, YPrime, L]);
+        assert_eq!(CubieCube::try_from(cube), Err(FaceletError::RotatedCenters));
+        let cubies = CubieCube::relative_to_centers(&cube).unwrap();
+        let normalized = Cube::from(cubies);
+
+        // every sticker gets the default color of the face whose center has its color
+        let color_of = |center| {
+            let face = (0..6)
+                .find(|&face| cube.face(face).get(4) == center)
+                .unwrap();
+            Cube::default().face(face).get(4)
+        };
+        let mut renamed = cube;
+        for face in 0..6 {
+            for index in 0..9 {
+                renamed
+                    .face_mut(face)
+                    .set(index, color_of(cube.face(face).get(index)));
+            }
+        }
+        assert_ne!(normalized, cube);
+        assert_eq!(normalized, renamed);
+        assert_eq!(CubieCube::try_from(normalized), Ok(cubies));
+        assert_eq!(Cube::from(cubies).validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid_stickers() {
+        let mut cube = Cube::default();
+        cube.top_mut().set(8, Color::Yellow);
+        assert_eq!(
+            CubieCube::try_from(cube),
+            Err(FaceletError::InvalidCorner(0))
+        );
+
+        let mut cube = Cube::default();
+        cube.front_mut().set(1, Color::Yellow);
+        assert_eq!(CubieCube::try_from(cube), Err(FaceletError::InvalidEdge(1)));
+
+        let mut cube = Cube::default();
+        cube.front_mut().set(4, Color::Blue);
+        assert_eq!(
+            CubieCube::try_from(cube),
+            Err(FaceletError::DuplicateCenter(Color::Blue))
+        );
+    }
+
+    #[test]
+    fn slice_sorted_round_trip() {
+        assert_eq!(CubieCube::SOLVED.slice_sorted(), 0);
+
+        let mut cube = CubieCube::SOLVED;
+        for slice_sorted in 0..11880 {
+            cube.set_slice_sorted(slice_sorted);
+            assert_eq!(cube.slice_sorted(), slice_sorted);
+        }
+    }
+
+    #[test]
+    fn coordinates_round_trip() {
+        let mut cube = CubieCube::SOLVED;
+        for rank in [0, 1, 5039, 20160, 40319] {
+            cube.set_corner_permutation(rank);
+            assert_eq!(cube.corner_permutation(), rank);
+        }
+        for twist in [0, 1, 1000, 2186] {
+            cube.set_twist(twist);
+            assert_eq!(cube.twist(), twist);
+            assert_eq!(cube.co.iter().map(|&o| o as u32).sum::<u32>() % 3, 0);
+        }
+        for flip in [0, 1, 1000, 2047] {
+            cube.set_flip(flip);
+            assert_eq!(cube.flip(), flip);
+            assert_eq!(cube.eo.iter().map(|&o| o as u32).sum::<u32>() % 2, 0);
+        }
+        for rank in [0, 1, 5039, 40319] {
+            cube.set_ud_edges(rank);
+            assert_eq!(cube.ud_edges(), rank);
+        }
+    }
+}
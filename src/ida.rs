@@ -0,0 +1,627 @@
+use std::cmp::Ordering;
+
+use fxhash::FxHashMap;
+use rayon::prelude::*;
+
+use crate::{
+    cube::{Cube, ValidationError},
+    cubie::{binomial, to_move, CubieCube, FACE_TURNS, MOVE_COUNT},
+    symmetry::{conjugate, conjugate_move, SYMMETRY_COUNT},
+    turn::Move,
+};
+
+const CORNER_PERMUTATIONS: usize = 40320;
+const TWISTS: usize = 2187;
+/// The number of ways to place 6 distinguishable edges in 12 positions (12! / 6!).
+const EDGE_PLACEMENTS: usize = 665280;
+const EDGE_ORIENTATIONS: usize = 64;
+/// The number of ways to sort the twelve edges into the three middle slices, four into each (12! / 4!^3).
+const EDGE_SLICES: usize = 34650;
+const FLIPS: usize = 2048;
+/// The middle slice every edge belongs to: 0 between the top and bottom face, 1 between the left and right
+/// face and 2 between the front and back face.
+const EDGE_SLICE: [u8; 12] = [2, 1, 2, 1, 2, 1, 2, 1, 0, 0, 0, 0];
+/// The distance up to which [`IdaSolver`] knows the exact distance of every position.
+const NEAR_DEPTH: u8 = 5;
+
+/// A table of 4 bit values, which is enough to store the distance of any state to the solved state.
+#[derive(Debug)]
+struct Nibbles(Vec<u8>);
+
+impl Nibbles {
+    const UNKNOWN: u8 = 0xF;
+
+    fn new(len: usize) -> Self {
+        Self(vec![0xFF; len.div_ceil(2)])
+    }
+
+    fn get(&self, index: usize) -> u8 {
+        (self.0[index / 2] >> ((index % 2) * 4)) & 0xF
+    }
+
+    fn set(&mut self, index: usize, value: u8) {
+        let shift = (index % 2) * 4;
+        let byte = &mut self.0[index / 2];
+        *byte = (*byte & !(0xF << shift)) | (value << shift);
+    }
+}
+
+/// Fills a pattern database of `len` states by breadth first search from the solved state.
+/// `neighbors` has to write the indices of all states that are one move away from the given one.
+fn generate(
+    len: usize,
+    solved: usize,
+    neighbors: impl Fn(usize, &mut [usize; MOVE_COUNT]),
+) -> Nibbles {
+    let mut table = Nibbles::new(len);
+    table.set(solved, 0);
+
+    let mut adjacent = [0; MOVE_COUNT];
+    let mut filled = 1;
+    let mut depth = 0;
+    while filled < len {
+        // once most states are known, it's cheaper to look for unknown states next to the current layer
+        // than to expand the whole layer
+        let backwards = filled > len / 2;
+        for index in 0..len {
+            if backwards {
+                if table.get(index) != Nibbles::UNKNOWN {
+                    continue;
+                }
+                neighbors(index, &mut adjacent);
+                if adjacent.iter().any(|&n| table.get(n) == depth) {
+                    table.set(index, depth + 1);
+                    filled += 1;
+                }
+            } else {
+                if table.get(index) != depth {
+                    continue;
+                }
+                neighbors(index, &mut adjacent);
+                for &n in &adjacent {
+                    if table.get(n) == Nibbles::UNKNOWN {
+                        table.set(n, depth + 1);
+                        filled += 1;
+                    }
+                }
+            }
+        }
+        depth += 1;
+    }
+
+    table
+}
+
+/// Ranks the positions of six distinct edges among the twelve edge positions.
+fn placement_rank(positions: &[u8; 6]) -> u32 {
+    let mut used = 0u32;
+    positions.iter().enumerate().fold(0, |acc, (i, &p)| {
+        let smaller_unused = p as u32 - (used & ((1 << p) - 1)).count_ones();
+        used |= 1 << p;
+        acc * (12 - i as u32) + smaller_unused
+    })
+}
+
+/// The inverse of [`placement_rank`].
+fn placement_unrank(mut rank: u32) -> [u8; 6] {
+    let mut digits = [0; 6];
+    for i in (0..6).rev() {
+        digits[i] = (rank % (12 - i as u32)) as u8;
+        rank /= 12 - i as u32;
+    }
+
+    let mut used = 0u32;
+    digits.map(|digit| {
+        let position = (0..12)
+            .filter(|&p| used & (1 << p) == 0)
+            .nth(digit as usize)
+            .unwrap();
+        used |= 1 << position;
+        position
+    })
+}
+
+/// Ranks the middle slice the edge at every position belongs to: first the positions of the edges of
+/// slice 0 among all twelve, then the positions of the edges of slice 1 among the other eight.
+fn slices_rank(slices: &[u8; 12]) -> u16 {
+    let mut rank = 0;
+    for slice in 0..2 {
+        rank *= binomial(8, 4);
+        let free = slices.iter().filter(|&&s| s >= slice);
+        let mut found = 0;
+        for (i, _) in free.enumerate().filter(|&(_, &s)| s == slice) {
+            found += 1;
+            rank += binomial(i, found);
+        }
+    }
+    rank
+}
+
+/// The inverse of [`slices_rank`].
+fn slices_unrank(rank: u16) -> [u8; 12] {
+    let mut slices = [2; 12];
+    for (slice, mut rank) in [(0, rank / binomial(8, 4)), (1, rank % binomial(8, 4))] {
+        let free = (0..12).filter(|&p| slices[p] == 2).collect::<Vec<_>>();
+        // the largest binomial coefficient that still fits belongs to the last edge of the slice
+        let mut left = 4;
+        for (i, &position) in free.iter().enumerate().rev() {
+            if left > 0 && binomial(i, left) <= rank {
+                rank -= binomial(i, left);
+                left -= 1;
+                slices[position] = slice;
+            }
+        }
+    }
+    slices
+}
+
+/// The state of the search, stored as the coordinates the pattern databases are indexed by.
+#[derive(Copy, Clone, Debug)]
+struct Node {
+    corner_permutation: u16,
+    twist: u16,
+    /// The placement and orientation of the first and the last six edges.
+    edges: [(u32, u8); 2],
+    /// The middle slice of every edge, by [`slices_rank`].
+    slices: u16,
+    flip: u16,
+}
+
+impl Node {
+    /// All coordinates packed into a single number, which identifies the position.
+    fn key(&self) -> u128 {
+        let [(first, first_flips), (last, last_flips)] = self.edges;
+        (self.corner_permutation as u128)
+            | (self.twist as u128) << 16
+            | (first as u128) << 32
+            | (first_flips as u128) << 52
+            | (last as u128) << 58
+            | (last_flips as u128) << 78
+    }
+}
+
+/// An optimal solver using iterative deepening A* (Korf's algorithm).
+///
+/// The search is guided by four pattern databases: one for all corners, one each for the first and last
+/// six edges, and one for the orientation of all edges together with the middle slice each of them is in.
+/// Each stores the exact number of moves needed to solve its pieces, so the maximum of the four never
+/// overestimates the real distance and the first solution found is optimal.
+///
+/// If the cube looks the same from several sides, like the superflip, moves that are symmetric to each
+/// other lead to equally long solutions, so only the first one of them is searched.
+///
+/// Close to the solved cube, the databases are often far below the real distance. So the solver also
+/// keeps the exact distance of every position up to five moves away, and knows that any other
+/// position is further away than that. This cuts the last few moves off every branch of the search.
+///
+/// Distances are measured in the half turn metric, where a half turn counts as a single move.
+///
+/// Every move a position needs makes the search about twelve times slower. The superflip, which needs
+/// 20 moves, takes about a day on one core, so the hardest positions are out of reach in practice.
+///
+/// Building the tables takes several seconds and about 175 MB of memory, so a solver should be reused.
+#[derive(Debug)]
+pub struct IdaSolver {
+    corner_permutation_moves: Vec<[u16; MOVE_COUNT]>,
+    twist_moves: Vec<[u16; MOVE_COUNT]>,
+    /// For every edge placement and move: the new placement shifted left by 6, combined with
+    /// a mask of the edges that get flipped.
+    edge_moves: Vec<[u32; MOVE_COUNT]>,
+    slice_moves: Vec<[u16; MOVE_COUNT]>,
+    flip_moves: Vec<[u16; MOVE_COUNT]>,
+    /// The move every move turns into when the cube is looked at with a symmetry.
+    symmetric_moves: [[u8; MOVE_COUNT]; SYMMETRY_COUNT],
+    corners: Nibbles,
+    edges: [Nibbles; 2],
+    /// Distances indexed by the middle slice and orientation of every edge.
+    slice_flips: Nibbles,
+    /// The distance of every position up to [`NEAR_DEPTH`] moves away from the solved cube, by
+    /// [`Node::key`].
+    near: FxHashMap<u128, u8>,
+}
+
+impl IdaSolver {
+    pub fn new() -> Self {
+        let moves = FACE_TURNS;
+
+        let corner_permutation_moves = (0..CORNER_PERMUTATIONS as u16)
+            .map(|rank| {
+                let mut cube = CubieCube::SOLVED;
+                cube.set_corner_permutation(rank);
+                moves.map(|m| cube.multiply(&m).corner_permutation())
+            })
+            .collect::<Vec<_>>();
+
+        let twist_moves = (0..TWISTS as u16)
+            .map(|twist| {
+                let mut cube = CubieCube::SOLVED;
+                cube.set_twist(twist);
+                moves.map(|m| cube.multiply(&m).twist())
+            })
+            .collect::<Vec<_>>();
+
+        // For every move and position, the position an edge moves to and whether it gets flipped.
+        let edge_targets = moves.map(|m| {
+            let mut targets = [(0, 0); 12];
+            for (to, (&from, &flip)) in m.ep.iter().zip(&m.eo).enumerate() {
+                targets[from as usize] = (to as u8, flip);
+            }
+            targets
+        });
+        let edge_moves = (0..EDGE_PLACEMENTS as u32)
+            .map(|rank| {
+                let positions = placement_unrank(rank);
+                std::array::from_fn(|m| {
+                    let mut flips = 0;
+                    let moved = std::array::from_fn(|i| {
+                        let (to, flip) = edge_targets[m][positions[i] as usize];
+                        flips |= (flip as u32) << i;
+                        to
+                    });
+                    (placement_rank(&moved) << 6) | flips
+                })
+            })
+            .collect::<Vec<[u32; MOVE_COUNT]>>();
+        let slice_moves = (0..EDGE_SLICES as u16)
+            .map(|rank| {
+                let slices = slices_unrank(rank);
+                moves.map(|m| slices_rank(&m.ep.map(|from| slices[from as usize])))
+            })
+            .collect::<Vec<_>>();
+        let flip_moves = (0..FLIPS as u16)
+            .map(|flip| {
+                let mut cube = CubieCube::SOLVED;
+                cube.set_flip(flip);
+                moves.map(|m| cube.multiply(&m).flip())
+            })
+            .collect::<Vec<_>>();
+        let symmetric_moves = std::array::from_fn(|symmetry| {
+            std::array::from_fn(|m| {
+                let conjugated = conjugate_move(to_move(m), symmetry);
+                (0..MOVE_COUNT).find(|&c| to_move(c) == conjugated).unwrap() as u8
+            })
+        });
+
+        let corners = generate(CORNER_PERMUTATIONS * TWISTS, 0, |index, adjacent| {
+            let permutation = index / TWISTS;
+            let twist = index % TWISTS;
+            for (m, n) in adjacent.iter_mut().enumerate() {
+                *n = corner_permutation_moves[permutation][m] as usize * TWISTS
+                    + twist_moves[twist][m] as usize;
+            }
+        });
+        let edges = [0, 6].map(|first| {
+            let solved = edge_coordinate(&CubieCube::SOLVED, first);
+            generate(
+                EDGE_PLACEMENTS * EDGE_ORIENTATIONS,
+                solved.0 as usize * EDGE_ORIENTATIONS,
+                |index, adjacent| {
+                    let placement = index / EDGE_ORIENTATIONS;
+                    let orientation = index % EDGE_ORIENTATIONS;
+                    for (m, n) in adjacent.iter_mut().enumerate() {
+                        let moved = edge_moves[placement][m] as usize;
+                        *n = (moved >> 6) * EDGE_ORIENTATIONS + (orientation ^ (moved & 0x3F));
+                    }
+                },
+            )
+        });
+        let slice_flips = generate(
+            EDGE_SLICES * FLIPS,
+            slices_rank(&EDGE_SLICE) as usize * FLIPS,
+            |index, adjacent| {
+                let slices = index / FLIPS;
+                let flip = index % FLIPS;
+                for (m, n) in adjacent.iter_mut().enumerate() {
+                    *n = slice_moves[slices][m] as usize * FLIPS + flip_moves[flip][m] as usize;
+                }
+            },
+        );
+
+        let mut solver = Self {
+            corner_permutation_moves,
+            twist_moves,
+            edge_moves,
+            slice_moves,
+            flip_moves,
+            symmetric_moves,
+            corners,
+            edges,
+            slice_flips,
+            near: FxHashMap::default(),
+        };
+        let solved = Node::from(&CubieCube::SOLVED);
+        let mut near = FxHashMap::default();
+        near.insert(solved.key(), 0);
+        let mut layer = vec![solved];
+        for depth in 1..=NEAR_DEPTH {
+            let mut next = Vec::new();
+            for node in &layer {
+                for m in 0..MOVE_COUNT {
+                    let moved = solver.apply(node, m);
+                    near.entry(moved.key()).or_insert_with(|| {
+                        next.push(moved);
+                        depth
+                    });
+                }
+            }
+            layer = next;
+        }
+        solver.near = near;
+        solver
+    }
+
+    /// Searches for an optimal solution of `cube` that is at most `max_depth` face turns long.
+    ///
+    /// Returns `None` if there is no such solution, and an error if `cube` can't be solved at all.
+    pub fn solve(&self, cube: Cube, max_depth: u8) -> Result<Option<Vec<Move>>, ValidationError> {
+        cube.validate()?;
+        let cubies = CubieCube::relative_to_centers(&cube)?;
+        let root = Node::from(&cubies);
+        let normalized = Cube::from(cubies);
+        let symmetries = (0..SYMMETRY_COUNT)
+            .filter(|&symmetry| conjugate(&normalized, symmetry) == normalized)
+            .fold(0, |mask, symmetry| mask | 1 << symmetry);
+
+        let mut path = Vec::new();
+        for bound in self.heuristic(&root)..=max_depth {
+            if self.search(root, 0, bound, &mut path, symmetries) {
+                return Ok(Some(path.into_iter().map(to_move).collect()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Like [`IdaSolver::solve`], but solves all `cubes` in parallel on rayon's global thread pool. The
+    /// solutions are in the same order as the cubes.
+    pub fn solve_batch(
+        &self,
+        cubes: &[Cube],
+        max_depth: u8,
+    ) -> Vec<Result<Option<Vec<Move>>, ValidationError>> {
+        cubes
+            .par_iter()
+            .map(|&cube| self.solve(cube, max_depth))
+            .collect()
+    }
+
+    /// `symmetries` is the set of symmetries that leave both the scrambled cube and the moves in `path`
+    /// unchanged, as a bit mask.
+    fn search(
+        &self,
+        node: Node,
+        depth: u8,
+        bound: u8,
+        path: &mut Vec<usize>,
+        symmetries: u64,
+    ) -> bool {
+        let estimate = self.heuristic(&node);
+        if estimate == 0 {
+            return true;
+        }
+        if depth + estimate > bound {
+            return false;
+        }
+
+        let last_face = path.last().map(|&m| m / 3);
+        for m in 0..MOVE_COUNT {
+            let face = m / 3;
+            if let Some(last_face) = last_face {
+                // turning the same face twice in a row, or opposite faces in both orders is never optimal
+                if face == last_face || (face % 3 == last_face % 3 && face < last_face) {
+                    continue;
+                }
+            }
+            let Some(symmetries) = self.fixing(symmetries, m) else {
+                continue;
+            };
+
+            path.push(m);
+            if self.search(self.apply(&node, m), depth + 1, bound, path, symmetries) {
+                return true;
+            }
+            path.pop();
+        }
+
+        false
+    }
+
+    /// Returns the symmetries out of `symmetries` that turn `m` into itself, or `None` if one of them
+    /// turns it into a smaller move. The symmetries leave the position unchanged, so they turn every
+    /// solution that starts with `m` into one that starts with that smaller move, which has already been
+    /// searched.
+    fn fixing(&self, symmetries: u64, m: usize) -> Option<u64> {
+        let mut fixing = 0;
+        let mut rest = symmetries;
+        while rest != 0 {
+            let symmetry = rest.trailing_zeros() as usize;
+            rest &= rest - 1;
+            match self.symmetric_moves[symmetry][m].cmp(&(m as u8)) {
+                Ordering::Less => return None,
+                Ordering::Equal => fixing |= 1 << symmetry,
+                Ordering::Greater => {}
+            }
+        }
+        Some(fixing)
+    }
+
+    fn apply(&self, node: &Node, m: usize) -> Node {
+        Node {
+            corner_permutation: self.corner_permutation_moves[node.corner_permutation as usize][m],
+            twist: self.twist_moves[node.twist as usize][m],
+            edges: node.edges.map(|(placement, orientation)| {
+                let moved = self.edge_moves[placement as usize][m];
+                (moved >> 6, orientation ^ (moved & 0x3F) as u8)
+            }),
+            slices: self.slice_moves[node.slices as usize][m],
+            flip: self.flip_moves[node.flip as usize][m],
+        }
+    }
+
+    fn heuristic(&self, node: &Node) -> u8 {
+        let corners = self
+            .corners
+            .get(node.corner_permutation as usize * TWISTS + node.twist as usize);
+        let estimate = node
+            .edges
+            .iter()
+            .zip(&self.edges)
+            .map(|(&(placement, orientation), table)| {
+                table.get(placement as usize * EDGE_ORIENTATIONS + orientation as usize)
+            })
+            .fold(corners, u8::max)
+            .max(
+                self.slice_flips
+                    .get(node.slices as usize * FLIPS + node.flip as usize),
+            );
+        if estimate > NEAR_DEPTH {
+            return estimate;
+        }
+        // positions that aren't near the solved cube are at least one move further away
+        self.near
+            .get(&node.key())
+            .copied()
+            .unwrap_or(NEAR_DEPTH + 1)
+    }
+}
+
+impl Default for IdaSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&CubieCube> for Node {
+    fn from(cubies: &CubieCube) -> Self {
+        Self {
+            corner_permutation: cubies.corner_permutation(),
+            twist: cubies.twist(),
+            edges: [edge_coordinate(cubies, 0), edge_coordinate(cubies, 6)],
+            slices: slices_rank(&cubies.ep.map(|edge| EDGE_SLICE[edge as usize])),
+            flip: cubies.flip(),
+        }
+    }
+}
+
+/// The placement and orientation of the six edges starting with edge `first`.
+fn edge_coordinate(cube: &CubieCube, first: u8) -> (u32, u8) {
+    let mut positions = [0; 6];
+    let mut orientation = 0;
+    for (position, (&edge, &flip)) in cube.ep.iter().zip(&cube.eo).enumerate() {
+        if (first..first + 6).contains(&edge) {
+            let i = (edge - first) as usize;
+            positions[i] = position as u8;
+            orientation |= flip << i;
+        }
+    }
+
+    (placement_rank(&positions), orientation)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+    use crate::turn::{Move::*, Turnable};
+
+    fn solver() -> &'static IdaSolver {
+        static SOLVER: OnceLock<IdaSolver> = OnceLock::new();
+        SOLVER.get_or_init(IdaSolver::new)
+    }
+
+    /// Solves `cube` and checks that the solution is `face_turns` long in the half turn metric.
+    fn assert_solves(mut cube: Cube, face_turns: usize) {
+        let solution = solver()
+            .solve(cube, 20)
+            .unwrap()
+            .expect("no solution found");
+        assert_eq!(solution.len(), face_turns, "{solution:?}");
+
+        cube.perform_all(&solution);
+        assert_eq!(cube, Cube::default());
+    }
+
+    #[test]
+    fn already_solved() {
+        assert_solves(Cube::default(), 0);
+    }
+
+    #[test]
+    fn sune() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, RPrime, U, R, U, U, RPrime]);
+
+        assert_solves(cube, 7);
+    }
+
+    #[test]
+    fn checkerboard() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, R, L, L, F, F, B, B, U, U, D, D]);
+
+        assert_solves(cube, 6);
+    }
+
+    #[test]
+    fn batch() {
+        let mut sune = Cube::default();
+        sune.perform_all(&[R, U, RPrime, U, R, U, U, RPrime]);
+        let mut checkerboard = Cube::default();
+        checkerboard.perform_all(&[R2, L2, F2, B2, U2, D2]);
+
+        let lengths = solver()
+            .solve_batch(&[sune, Cube::default(), checkerboard], 20)
+            .into_iter()
+            .map(|solution| solution.unwrap().unwrap().len())
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, [7, 0, 6]);
+    }
+
+    #[test]
+    fn twelve_turns() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, FPrime, L, L, D, BPrime, R, UPrime, F, L, DPrime, B, B]);
+
+        assert_solves(cube, 12);
+    }
+
+    /// The superflip, which is its own inverse, so this is a solution of it as well.
+    const SUPERFLIP: [Move; 28] = [
+        U, R, R, F, B, R, B, B, R, U, U, L, B, B, R, UPrime, DPrime, R, R, F, RPrime, L, B, B, U,
+        U, F, F,
+    ];
+
+    fn superflip() -> Cube {
+        let mut cube = Cube::default();
+        cube.perform_all(&SUPERFLIP);
+        cube
+    }
+
+    /// The superflip needs 20 face turns and all of its pieces are solved except for the edge
+    /// orientation, which makes it about the hardest position there is for the pattern databases. Every
+    /// bound takes about twelve times longer than the one before, so this only goes as far as the
+    /// search gets in a few seconds. It only shows that there's no solution of up to 15 moves, not that
+    /// the solver finds the optimal one, which is what `superflip_optimal` checks.
+    #[test]
+    fn superflip_lower_bound() {
+        let cube = superflip();
+        let root = Node::from(&CubieCube::try_from(cube).unwrap());
+        assert_eq!(solver().heuristic(&root), 11);
+        assert_eq!(solver().solve(cube, 15), Ok(None));
+
+        let mut solved = cube;
+        solved.perform_all(&SUPERFLIP);
+        assert!(solved.is_solved());
+    }
+
+    /// Bound 16 takes about 50 seconds on one core, so at twelve times longer per bound, proving that the
+    /// superflip can't be solved in 19 moves still takes about a day with these pattern databases. This
+    /// only runs when asked for with `--ignored`, so nothing that runs by default checks that the
+    /// superflip is solved optimally.
+    #[test]
+    #[ignore = "takes about a day on one core with the current pattern databases"]
+    fn superflip_optimal() {
+        assert_solves(superflip(), 20);
+    }
+}
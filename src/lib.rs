@@ -0,0 +1,45 @@
+//! Representing, turning and solving 3x3 rubiks cubes.
+//!
+//! A [`Cube`] stores the colors of its six [`Face`]s and can be turned with any [`Move`] through the
+//! [`Turnable`] trait. [`Solver`] finds the shortest solution of a scramble with a bidirectional breadth
+//! first search, which works well for up to about 16 moves. For harder scrambles, [`ida::IdaSolver`]
+//! finds optimal solutions and [`two_phase::TwoPhaseSolver`] finds short ones quickly.
+//! [`cfop::CfopSolver`] solves cubes step by step like people do, and [`beginner::BeginnerSolver`] does the
+//! same with the layer by layer method, explaining every step.
+//! Other sizes, from 2x2 to 7x7, are represented by [`nxn::NxNCube`]. 2x2s are solved optimally by
+//! [`pocket::PocketSolver`], and 4x4s with the reduction method by [`reduction::ReductionSolver`].
+//!
+//! ```
+//! use cubers::{Cube, Move, Solver, Turnable};
+//!
+//! let mut cube = Cube::default();
+//! cube.perform_all(&[Move::R, Move::U]);
+//!
+//! let solution = Solver::new().solve(cube, 4)?.unwrap();
+//! cube.perform_all(&solution);
+//! assert!(cube.is_solved());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod beginner;
+pub mod cfop;
+pub mod cube;
+pub mod cubie;
+pub mod ida;
+mod method;
+pub mod notation;
+pub mod nxn;
+pub mod pocket;
+pub mod reduction;
+pub mod scramble;
+pub mod solver;
+mod spill;
+pub mod symmetry;
+pub mod table;
+pub mod turn;
+pub mod two_phase;
+
+pub use cube::{Color, Cube, Face, ValidationError};
+pub use notation::MoveSequence;
+pub use solver::Solver;
+pub use turn::{Metric, Move, Turnable};
@@ -1,17 +1,234 @@
-use std::time::Instant;
+use std::{
+    error::Error,
+    io::Read,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
-use cube::{Color, Cube};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use cubers::{
+    ida::IdaSolver,
+    notation::NxNSequence,
+    nxn::NxNCube,
+    scramble::{RandomMoveScrambler, RandomStateScrambler},
+    solver::Progress,
+    table::SolvedTable,
+    two_phase::TwoPhaseSolver,
+    Color, Cube, Metric, Move, MoveSequence, Solver, Turnable,
+};
 use image::{ImageBuffer, Rgb};
-use turn::Turnable;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use serde_json::json;
 
-use crate::solver::Solver;
+/// Solve, scramble and draw rubiks cubes.
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Find a solution for a scrambled cube.
+    Solve {
+        #[command(flatten)]
+        input: Input,
+        #[arg(short, long, value_enum, default_value_t = SolverKind::TwoPhase)]
+        solver: SolverKind,
+        /// The longest solution to look for. Defaults to 12 for `bfs`, 20 for `ida` and 22 for `two-phase`.
+        #[arg(short = 'd', long)]
+        max_depth: Option<u8>,
+        #[command(flatten)]
+        bfs: BfsOptions,
+        /// How many seconds the `two-phase` solver looks for solutions, and then for shorter ones.
+        #[arg(long, default_value_t = 1.0)]
+        timeout: f64,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Print a random scramble.
+    Scramble {
+        /// The number of moves in the scramble.
+        #[arg(short, long, default_value_t = 25)]
+        length: usize,
+        /// Scramble to a position picked uniformly at random, like official WCA scrambles, instead of
+        /// turning `length` random faces.
+        #[arg(long)]
+        random_state: bool,
+        /// Skip random states that can be solved in fewer moves. No position needs more than 20, and
+        /// every random state is searched optimally up to this depth, which gets slow above about 10.
+        #[arg(
+            long,
+            default_value_t = 2,
+            requires = "random_state",
+            value_parser = clap::value_parser!(u8).range(0..=20)
+        )]
+        min_length: u8,
+        /// Only turn the layers of these moves, e.g. "R U" for ⟨R, U⟩ scrambles.
+        #[arg(long, conflicts_with = "random_state")]
+        moves: Option<MoveSequence>,
+        /// Makes the scramble reproducible.
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Precompute the positions close to the solved cube for the `bfs` solver, see `solve --table`.
+    Table {
+        #[arg(long, value_enum, default_value_t = MetricKind::Half)]
+        metric: MetricKind,
+        /// The distance up to which positions are stored. Every depth makes the table about 13 times
+        /// bigger.
+        #[arg(short = 'd', long, default_value_t = 6)]
+        depth: u8,
+        /// Where to write the table.
+        #[arg(short, long, default_value = "solved.table")]
+        output: PathBuf,
+    },
+    /// Draw the net of a cube as an image.
+    Render {
+        #[command(flatten)]
+        input: Input,
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(2..=7))]
+        size: u8,
+        /// Where to write the image.
+        #[arg(short, long, default_value = "out.png")]
+        output: PathBuf,
+    },
+    /// Print the facelets of a cube after applying moves to it.
+    Apply {
+        #[command(flatten)]
+        input: Input,
+        /// The number of layers of the cube, from 2 to 7. Facelet strings have their own size.
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(2..=7))]
+        size: u8,
+        /// Also draw the resulting cube to this image.
+        #[arg(short, long)]
+        image: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Check that a solution solves a cube. Exits with an error if it doesn't.
+    Verify {
+        #[command(flatten)]
+        input: Input,
+        /// The moves that are supposed to solve the cube.
+        #[arg(long)]
+        solution: String,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+}
+
+/// The cube a command works on.
+#[derive(Args, Debug)]
+struct Input {
+    /// A scramble like "R U R' U'", applied to a solved cube. Read from stdin if missing.
+    scramble: Vec<String>,
+    /// Read the input as a 54 character facelet string like Kociemba's solver uses, i.e.
+    /// "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB" for a solved cube.
+    #[arg(short, long)]
+    facelets: bool,
+}
+
+/// Options that only apply to the `bfs` solver.
+#[derive(Args, Debug)]
+struct BfsOptions {
+    /// The metric the `bfs` solver counts moves in.
+    #[arg(long, value_enum, default_value_t = MetricKind::Half)]
+    metric: MetricKind,
+    /// How many threads the `bfs` solver uses. Defaults to one per CPU.
+    #[arg(long)]
+    threads: Option<usize>,
+    /// How many megabytes the `bfs` solver keeps in memory before it moves the search to temporary
+    /// files. Unlimited by default.
+    #[arg(long)]
+    memory: Option<usize>,
+    /// Look up the positions close to the solved cube in a table written by the `table` command,
+    /// instead of searching them. The table decides the metric.
+    #[arg(long)]
+    table: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum SolverKind {
+    /// Bidirectional breadth first search. Only feasible for short scrambles.
+    Bfs,
+    /// Optimal solutions in the half turn metric, using pattern databases.
+    Ida,
+    /// Near optimal solutions within a few seconds (Kociemba's algorithm).
+    TwoPhase,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum MetricKind {
+    Quarter,
+    Half,
+    Slice,
+}
+
+impl MetricKind {
+    fn metric(self) -> Metric {
+        match self {
+            MetricKind::Quarter => Metric::Quarter,
+            MetricKind::Half => Metric::Half,
+            MetricKind::Slice => Metric::Slice,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+impl Input {
+    fn text(&self) -> Result<String, Box<dyn Error>> {
+        Ok(if self.scramble.is_empty() {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            text
+        } else {
+            self.scramble.join(" ")
+        })
+    }
 
-mod cube;
-pub mod solver;
-pub mod turn;
+    fn read(&self) -> Result<Cube, Box<dyn Error>> {
+        let text = self.text()?;
+        if self.facelets {
+            Ok(text.trim().parse()?)
+        } else {
+            let scramble: MoveSequence = text.parse()?;
+            let mut cube = Cube::default();
+            cube.perform_all(&scramble.0);
+            Ok(cube)
+        }
+    }
 
-fn save_cube(cube: &Cube) {
-    let mut buf = ImageBuffer::new(4 * 3, 3 * 3);
+    /// Like [`Input::read`], for a cube of any size. Scrambles may contain inner layer moves.
+    fn read_nxn(&self, size: u8) -> Result<NxNCube, Box<dyn Error>> {
+        let text = self.text()?;
+        if self.facelets {
+            Ok(text.trim().parse()?)
+        } else {
+            let size = size as usize;
+            let scramble: NxNSequence = text.parse()?;
+            if let Some(m) = scramble.0.iter().find(|m| !m.fits(size)) {
+                return Err(format!("{m} doesn't fit on a {size}x{size} cube").into());
+            }
+            let mut cube = NxNCube::new(size);
+            cube.turn_all(&scramble.0);
+            Ok(cube)
+        }
+    }
+}
+
+fn save_cube(cube: &NxNCube, path: &Path) -> Result<(), Box<dyn Error>> {
+    let n = cube.size() as u32;
+    let mut buf = ImageBuffer::new(4 * n, 3 * n);
 
     let col_to_rgb = |col| match col {
         Color::White => Rgb([255u8, 255, 255]),
@@ -22,29 +239,196 @@ fn save_cube(cube: &Cube) {
         Color::Red => Rgb([255, 0, 0]),
     };
     buf.enumerate_pixels_mut().for_each(|(x, y, pixel)| {
-        if (3..6).contains(&y) {
-            let face = [Cube::LEFT, Cube::FRONT, Cube::RIGHT, Cube::BACK][x as usize / 3];
-            let index = (y - 3) * 3 + x - (x / 3) * 3;
-            *pixel = col_to_rgb(cube.face(face).get(index as usize));
-        } else if (3..6).contains(&x) {
-            let face = [Cube::TOP, Cube::FRONT, Cube::BOTTOM][y as usize / 3];
-            let index = (y - (y / 3) * 3) * 3 + (x - 3);
-            *pixel = col_to_rgb(cube.face(face).get(index as usize));
+        if (n..2 * n).contains(&y) {
+            let face = [Cube::LEFT, Cube::FRONT, Cube::RIGHT, Cube::BACK][(x / n) as usize];
+            *pixel = col_to_rgb(cube.get(face, (y - n) as usize, (x % n) as usize));
+        } else if (n..2 * n).contains(&x) {
+            let face = [Cube::TOP, Cube::FRONT, Cube::BOTTOM][(y / n) as usize];
+            *pixel = col_to_rgb(cube.get(face, (y % n) as usize, (x - n) as usize));
         }
     });
 
-    buf.save("out.png").unwrap();
+    buf.save(path)?;
+    Ok(())
 }
 
-fn main() {
-    use turn::Move::*;
+fn solve(
+    cube: Cube,
+    solver: SolverKind,
+    max_depth: Option<u8>,
+    bfs: &BfsOptions,
+    timeout: f64,
+    format: Format,
+) -> Result<Option<Vec<Move>>, Box<dyn Error>> {
+    match solver {
+        SolverKind::Bfs => {
+            let solver = match &bfs.table {
+                Some(path) => Solver::with_table(SolvedTable::load(path)?),
+                None => Solver::with_metric(bfs.metric.metric()),
+            };
+            let mut solver = solver.threads(bfs.threads.unwrap_or(0));
+            if let Some(megabytes) = bfs.memory {
+                solver = solver.memory_budget(megabytes << 20);
+            }
+            let mut search = solver.search(cube)?;
+            if format == Format::Text {
+                search = search.observer(|progress: &Progress| {
+                    eprintln!(
+                        "depth {}: {} positions, {:.0} nodes/s, {} MB",
+                        progress.depth,
+                        progress.positions,
+                        progress.nodes_per_second,
+                        progress.memory >> 20
+                    )
+                });
+            }
+            Ok(search.solve(max_depth.unwrap_or(12))?)
+        }
+        SolverKind::Ida => Ok(IdaSolver::new().solve(cube, max_depth.unwrap_or(20))?),
+        SolverKind::TwoPhase => Ok(TwoPhaseSolver::new().solve(
+            cube,
+            max_depth.unwrap_or(22),
+            Duration::from_secs_f64(timeout),
+        )?),
+    }
+}
 
-    let mut cube = Cube::default();
-    cube.perform_all(&[R, L, F, B, R, U, RPrime, UPrime, R, LPrime, U, U, D, D]);
-    save_cube(&cube);
+fn run(command: Command) -> Result<ExitCode, Box<dyn Error>> {
+    match command {
+        Command::Solve {
+            input,
+            solver,
+            max_depth,
+            bfs,
+            timeout,
+            format,
+        } => {
+            let cube = input.read()?;
+            // checked before building any tables
+            cube.validate()?;
+            let start = Instant::now();
+            let solution = solve(cube, solver, max_depth, &bfs, timeout, format)?.map(MoveSequence);
+            let elapsed = start.elapsed();
 
-    let mut solver = Solver::from_state(cube);
-    let start = Instant::now();
-    println!("{:?}", solver.solve(12));
-    println!("took {:?}", start.elapsed());
+            match (format, &solution) {
+                (Format::Json, _) => println!(
+                    "{}",
+                    json!({
+                        "solution": solution.as_ref().map(ToString::to_string),
+                        "length": solution.as_ref().map(|s| s.0.len()),
+                        "seconds": elapsed.as_secs_f64(),
+                    })
+                ),
+                (Format::Text, Some(solution)) => {
+                    println!("{solution}");
+                    eprintln!("{} moves, took {elapsed:?}", solution.0.len());
+                }
+                (Format::Text, None) => eprintln!("no solution found, took {elapsed:?}"),
+            }
+            Ok(if solution.is_some() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        Command::Scramble {
+            length,
+            random_state,
+            min_length,
+            moves,
+            seed,
+            format,
+        } => {
+            let mut rng: Box<dyn RngCore> = match seed {
+                Some(seed) => Box::new(StdRng::seed_from_u64(seed)),
+                None => Box::new(rand::rng()),
+            };
+            let scramble = if random_state {
+                RandomStateScrambler::new()
+                    .min_length(min_length)
+                    .scramble(&mut rng)
+            } else {
+                let scrambler = match moves {
+                    Some(moves) => RandomMoveScrambler::generated_by(&moves.0)?,
+                    None => RandomMoveScrambler::new(),
+                };
+                scrambler.scramble(length, &mut rng)
+            };
+            let scramble = MoveSequence(scramble);
+            match format {
+                Format::Text => println!("{scramble}"),
+                Format::Json => println!("{}", json!({ "scramble": scramble.to_string() })),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Table {
+            metric,
+            depth,
+            output,
+        } => {
+            let start = Instant::now();
+            let table = SolvedTable::generate(metric.metric(), depth);
+            table.save(&output)?;
+            eprintln!("{} classes, took {:?}", table.len(), start.elapsed());
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Render {
+            input,
+            size,
+            output,
+        } => {
+            save_cube(&input.read_nxn(size)?, &output)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Apply {
+            input,
+            size,
+            image,
+            format,
+        } => {
+            let cube = input.read_nxn(size)?;
+            if let Some(path) = image {
+                save_cube(&cube, &path)?;
+            }
+
+            match format {
+                Format::Text => println!("{cube}"),
+                Format::Json => println!(
+                    "{}",
+                    json!({ "facelets": cube.to_string(), "solved": cube.is_solved() })
+                ),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Verify {
+            input,
+            solution,
+            format,
+        } => {
+            let mut cube = input.read()?;
+            let solution: MoveSequence = solution.parse()?;
+            cube.perform_all(&solution.0);
+
+            let solved = cube.is_solved();
+            match format {
+                Format::Text => println!("{}", if solved { "solved" } else { "not solved" }),
+                Format::Json => println!("{}", json!({ "solved": solved })),
+            }
+            Ok(if solved {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,107 @@
+//! Piece tables shared by the solvers of the human methods in [`crate::cfop`] and [`crate::beginner`].
+//!
+//! Both methods solve a few pieces at a time, so they search tables of the positions and orientations of
+//! just those pieces rather than of the whole cube.
+
+use crate::cubie::{CubieCube, FACE_TURNS, MOVE_COUNT};
+
+/// The edges of the bottom face DR, DF, DL and DB, which make up the cross of the human methods.
+pub(crate) const CROSS: [u8; 4] = [4, 5, 6, 7];
+
+/// For every F2L slot (in the order of their corners DFR, DLF, DBL and DRB), the rotation about the
+/// vertical axis that turns it into the front right slot, and how each face turn after the rotation
+/// relates to a face turn without it.
+pub(crate) fn frames() -> [(CubieCube, [usize; MOVE_COUNT]); 4] {
+    let mut frames = [(CubieCube::SOLVED, [0; MOVE_COUNT]); 4];
+    let mut rotation = CubieCube::SOLVED;
+    for _ in 0..4 {
+        let relabeled = std::array::from_fn(|m| {
+            let turn = rotation
+                .multiply(&FACE_TURNS[m])
+                .multiply(&rotation.inverse());
+            FACE_TURNS.iter().position(|&t| t == turn).unwrap()
+        });
+        // the corner of the front right slot after the rotation is this one before it
+        frames[rotation.cp[4] as usize - 4] = (rotation, relabeled);
+        rotation = rotation.multiply(&CubieCube::ROTATIONS[1]);
+    }
+    frames
+}
+
+/// Where a piece goes with every face turn, by its position and orientation as a code in `0..24`: the
+/// position times the number of orientations plus the orientation. Corners and edges both happen to have 24
+/// codes.
+pub(crate) type PieceMoves = [[u8; MOVE_COUNT]; 24];
+
+/// The code of `corner`, see [`PieceMoves`].
+pub(crate) fn corner_code(cubies: &CubieCube, corner: u8) -> u8 {
+    let position = cubies.cp.iter().position(|&c| c == corner).unwrap();
+    position as u8 * 3 + cubies.co[position]
+}
+
+/// The code of `edge`, see [`PieceMoves`].
+pub(crate) fn edge_code(cubies: &CubieCube, edge: u8) -> u8 {
+    let position = cubies.ep.iter().position(|&e| e == edge).unwrap();
+    position as u8 * 2 + cubies.eo[position]
+}
+
+/// Where every corner or edge goes with every face turn, see [`PieceMoves`]. `pieces` returns the
+/// permutation and orientation of the pieces of a turn.
+pub(crate) fn piece_moves(
+    orientations: u8,
+    pieces: impl Fn(&CubieCube) -> (&[u8], &[u8]),
+) -> PieceMoves {
+    std::array::from_fn(|code| {
+        let (position, orientation) = (code as u8 / orientations, code as u8 % orientations);
+        std::array::from_fn(|m| {
+            let (permutation, orientations_after) = pieces(&FACE_TURNS[m]);
+            let to = permutation
+                .iter()
+                .position(|&from| from == position)
+                .unwrap();
+            to as u8 * orientations + (orientation + orientations_after[to]) % orientations
+        })
+    })
+}
+
+/// The index of the codes of some pieces in a table of [`distances`].
+pub(crate) fn index(codes: &[u8]) -> usize {
+    codes
+        .iter()
+        .fold(0, |index, &code| index * 24 + code as usize)
+}
+
+/// The number of face turns it takes to solve `pieces`, given as the moves of each piece and its solved
+/// code, for every combination of their codes. The table is indexed by the codes of the pieces as digits
+/// in base 24, see [`index`], and combinations where two pieces are in the same place are left at
+/// `u8::MAX`.
+pub(crate) fn distances(pieces: &[(&PieceMoves, u8)]) -> Vec<u8> {
+    let mut table = vec![u8::MAX; 24usize.pow(pieces.len() as u32)];
+    let solved: Vec<_> = pieces.iter().map(|&(_, code)| code).collect();
+    table[index(&solved)] = 0;
+
+    let mut frontier = vec![index(&solved)];
+    let mut codes = vec![0; pieces.len()];
+    for distance in 1.. {
+        if frontier.is_empty() {
+            break;
+        }
+        let mut next = Vec::new();
+        for &position in &frontier {
+            for m in 0..MOVE_COUNT {
+                let mut rest = position;
+                for (i, (moves, _)) in pieces.iter().enumerate().rev() {
+                    codes[i] = moves[rest % 24][m];
+                    rest /= 24;
+                }
+                let moved = index(&codes);
+                if table[moved] == u8::MAX {
+                    table[moved] = distance;
+                    next.push(moved);
+                }
+            }
+        }
+        frontier = next;
+    }
+    table
+}
@@ -0,0 +1,835 @@
+//! Reading and writing moves in standard (Singmaster / WCA) notation.
+//!
+//! Besides plain moves like `R U R' U'`, `R2`, `Rw` or `r`, sequences can contain:
+//! - groups that are repeated or inverted: `(R U R' U')3`, `(R U)'`
+//! - commutators and conjugates: `[R, U]` is `R U R' U'` and `[R: U]` is `R U R'`
+//! - comments: `// until the end of the line` and `/* between these */`
+//!
+//! [`NxNSequence`]s of moves for bigger cubes can also contain inner layer moves like `2R`, `3Rw` (the
+//! outer three layers) and `2-3Lw` (the second and third layer from the left).
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{nxn::NxNMove, turn::Move};
+
+/// The most moves a sequence can expand to, so a few characters like `(R U)999999999` can't use up all
+/// the memory.
+pub const MAX_MOVES: usize = 1 << 20;
+
+/// How deeply groups and commutators can be nested, so input like `((((…` can't overflow the stack.
+pub const MAX_NESTING: usize = 100;
+
+/// Why some text couldn't be read as moves, and where.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The index of the character (not byte) where the problem was found.
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A character that can't appear at this point.
+    UnexpectedCharacter(char),
+    /// A move is turned by an amount other than 1, 2 or 3 (written as `'`).
+    InvalidAmount(u32),
+    /// A bracket or comment was opened at `position` but never closed.
+    Unclosed(char),
+    /// A single move was expected, but there was nothing or more than one.
+    NotASingleMove,
+    /// An inner layer move turns layer 0, or a range of layers that ends before it starts.
+    InvalidLayers,
+    /// The sequence expands to more than [`MAX_MOVES`] moves, e.g. because a group is repeated too
+    /// often.
+    TooLong,
+    /// Groups or commutators are nested more than [`MAX_NESTING`] levels deep.
+    TooDeep,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {c:?}")?,
+            ParseErrorKind::InvalidAmount(n) => write!(f, "a move can't be turned {n} times")?,
+            ParseErrorKind::Unclosed(c) => write!(f, "{c:?} is never closed")?,
+            ParseErrorKind::NotASingleMove => write!(f, "expected exactly one move")?,
+            ParseErrorKind::InvalidLayers => write!(f, "invalid layers")?,
+            ParseErrorKind::TooLong => write!(f, "more than {MAX_MOVES} moves")?,
+            ParseErrorKind::TooDeep => write!(f, "nested more than {MAX_NESTING} levels deep")?,
+        }
+        write!(f, " at position {}", self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Move::*;
+
+        let notation = match self {
+            L => "L",
+            LPrime => "L'",
+            L2 => "L2",
+            R => "R",
+            RPrime => "R'",
+            R2 => "R2",
+            U => "U",
+            UPrime => "U'",
+            U2 => "U2",
+            D => "D",
+            DPrime => "D'",
+            D2 => "D2",
+            F => "F",
+            FPrime => "F'",
+            F2 => "F2",
+            B => "B",
+            BPrime => "B'",
+            B2 => "B2",
+            M => "M",
+            MPrime => "M'",
+            M2 => "M2",
+            E => "E",
+            EPrime => "E'",
+            E2 => "E2",
+            S => "S",
+            SPrime => "S'",
+            S2 => "S2",
+            Lw => "Lw",
+            LwPrime => "Lw'",
+            Lw2 => "Lw2",
+            Rw => "Rw",
+            RwPrime => "Rw'",
+            Rw2 => "Rw2",
+            Uw => "Uw",
+            UwPrime => "Uw'",
+            Uw2 => "Uw2",
+            Dw => "Dw",
+            DwPrime => "Dw'",
+            Dw2 => "Dw2",
+            Fw => "Fw",
+            FwPrime => "Fw'",
+            Fw2 => "Fw2",
+            Bw => "Bw",
+            BwPrime => "Bw'",
+            Bw2 => "Bw2",
+            X => "x",
+            XPrime => "x'",
+            X2 => "x2",
+            Y => "y",
+            YPrime => "y'",
+            Y2 => "y2",
+            Z => "z",
+            ZPrime => "z'",
+            Z2 => "z2",
+        };
+        f.write_str(notation)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s).parse_single()
+    }
+}
+
+impl Display for NxNMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            NxNMove::Move(m) => write!(f, "{m}"),
+            NxNMove::Layers { turn, first, last } => {
+                let turn = turn.to_string();
+                let (face, amount) = turn.split_at(1);
+                match (first, last) {
+                    _ if first == last => write!(f, "{first}{turn}"),
+                    (1, _) => write!(f, "{last}{face}w{amount}"),
+                    _ => write!(f, "{first}-{last}{face}w{amount}"),
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for NxNMove {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s).parse_single()
+    }
+}
+
+/// A sequence of moves, like a scramble or an algorithm.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MoveSequence(pub Vec<Move>);
+
+impl MoveSequence {
+    /// Returns the sequence that undoes this one.
+    pub fn inverse(&self) -> Self {
+        Self(self.0.iter().rev().map(|m| m.inverse()).collect())
+    }
+}
+
+impl From<Vec<Move>> for MoveSequence {
+    fn from(moves: Vec<Move>) -> Self {
+        Self(moves)
+    }
+}
+
+impl Display for MoveSequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, m) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{m}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MoveSequence {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s).parse_all().map(Self)
+    }
+}
+
+/// Reads an algorithm that is written out in the crate, and therefore valid.
+pub(crate) fn parse(algorithm: &str) -> Vec<Move> {
+    algorithm.parse::<MoveSequence>().unwrap().0
+}
+
+/// A sequence of moves for a cube of any size, see [`NxNCube`](crate::nxn::NxNCube).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NxNSequence(pub Vec<NxNMove>);
+
+impl NxNSequence {
+    /// Returns the sequence that undoes this one.
+    pub fn inverse(&self) -> Self {
+        Self(self.0.iter().rev().map(|m| m.inverse()).collect())
+    }
+
+    /// Whether every move can be made on a cube with `size` layers.
+    pub fn fits(&self, size: usize) -> bool {
+        self.0.iter().all(|m| m.fits(size))
+    }
+}
+
+impl From<Vec<NxNMove>> for NxNSequence {
+    fn from(moves: Vec<NxNMove>) -> Self {
+        Self(moves)
+    }
+}
+
+impl From<MoveSequence> for NxNSequence {
+    fn from(moves: MoveSequence) -> Self {
+        Self(moves.0.into_iter().map(NxNMove::Move).collect())
+    }
+}
+
+impl Display for NxNSequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, m) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{m}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NxNSequence {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s).parse_all().map(Self)
+    }
+}
+
+/// The moves a [`Parser`] can read.
+trait Token: Copy {
+    /// Whether a move can start with `c`.
+    fn starts_with(c: char) -> bool;
+
+    /// Parses a single move with its amount, starting at a character for which `starts_with` is true.
+    fn parse(parser: &mut Parser) -> Result<Self, ParseError>;
+
+    fn inverse(self) -> Self;
+}
+
+impl Token for Move {
+    fn starts_with(c: char) -> bool {
+        "LRUDFBMESlrudfbxyz".contains(c)
+    }
+
+    fn parse(parser: &mut Parser) -> Result<Self, ParseError> {
+        parser.parse_move()
+    }
+
+    fn inverse(self) -> Self {
+        self.inverse()
+    }
+}
+
+impl Token for NxNMove {
+    fn starts_with(c: char) -> bool {
+        c.is_ascii_digit() || Move::starts_with(c)
+    }
+
+    /// Parses a 3x3 move, or `nR`, `nRw` or `n-mRw` with any face and amount.
+    fn parse(parser: &mut Parser) -> Result<Self, ParseError> {
+        let start = parser.position;
+        let Some(first) = parser.parse_number()? else {
+            return parser.parse_move().map(NxNMove::Move);
+        };
+        let last = if parser.peek() == Some('-') {
+            parser.position += 1;
+            parser.parse_number()?
+        } else {
+            None
+        };
+
+        use Move::*;
+        #[rustfmt::skip]
+        let turns = match parser.peek() {
+            Some('L') => [L, LPrime, L2],
+            Some('R') => [R, RPrime, R2],
+            Some('U') => [U, UPrime, U2],
+            Some('D') => [D, DPrime, D2],
+            Some('F') => [F, FPrime, F2],
+            Some('B') => [B, BPrime, B2],
+            Some(c) => return Err(parser.error(ParseErrorKind::UnexpectedCharacter(c))),
+            None => return Err(parser.error(ParseErrorKind::NotASingleMove)),
+        };
+        parser.position += 1;
+        let wide = parser.peek() == Some('w');
+        if wide {
+            parser.position += 1;
+        }
+        let turn = parser.parse_amount(turns)?;
+
+        // `3R` is the third layer and `3Rw` the first three, but `2-3R` and `2-3Rw` are the same
+        let (first, last) = match (last, wide) {
+            (Some(last), _) => (first, last),
+            (None, false) => (first, first),
+            (None, true) => (1, first),
+        };
+        if first == 0 || first > last || last > u8::MAX as u32 {
+            return Err(ParseError {
+                position: start,
+                kind: ParseErrorKind::InvalidLayers,
+            });
+        }
+        Ok(NxNMove::layers(turn, first as u8, last as u8))
+    }
+
+    fn inverse(self) -> Self {
+        self.inverse()
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    /// How many groups and commutators the parser is currently inside of.
+    nesting: usize,
+}
+
+impl Parser {
+    fn new(s: &str) -> Self {
+        Self {
+            chars: s.chars().collect(),
+            position: 0,
+            nesting: 0,
+        }
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: self.position,
+            kind,
+        }
+    }
+
+    /// Parses the whole input as a sequence.
+    fn parse_all<T: Token>(&mut self) -> Result<Vec<T>, ParseError> {
+        let moves = self.parse_sequence()?;
+        match self.peek() {
+            None => Ok(moves),
+            Some(c) => Err(self.error(ParseErrorKind::UnexpectedCharacter(c))),
+        }
+    }
+
+    /// Parses the whole input as a single move, with optional whitespace around it.
+    fn parse_single<T: Token>(&mut self) -> Result<T, ParseError> {
+        self.skip_whitespace()?;
+        let start = self.position;
+        match self.peek() {
+            Some(c) if T::starts_with(c) => {}
+            _ => return Err(self.error(ParseErrorKind::NotASingleMove)),
+        }
+
+        let m = T::parse(self)?;
+        self.skip_whitespace()?;
+        if self.position == self.chars.len() {
+            Ok(m)
+        } else {
+            Err(ParseError {
+                position: start,
+                kind: ParseErrorKind::NotASingleMove,
+            })
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            match (self.peek(), self.chars.get(self.position + 1)) {
+                (Some(c), _) if c.is_whitespace() => self.position += 1,
+                (Some('/'), Some('/')) => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.position += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let start = self.position;
+                    self.position += 2;
+                    loop {
+                        match (self.peek(), self.chars.get(self.position + 1)) {
+                            (Some('*'), Some('/')) => {
+                                self.position += 2;
+                                break;
+                            }
+                            (Some(_), _) => self.position += 1,
+                            (None, _) => {
+                                return Err(ParseError {
+                                    position: start,
+                                    kind: ParseErrorKind::Unclosed('/'),
+                                })
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Parses moves and groups until the end of the input or a character that ends a group.
+    fn parse_sequence<T: Token>(&mut self) -> Result<Vec<T>, ParseError> {
+        let mut moves = Vec::new();
+        loop {
+            self.skip_whitespace()?;
+            match self.peek() {
+                None | Some(')' | ']' | ',' | ':') => return Ok(moves),
+                Some('(') => {
+                    let start = self.position;
+                    let group = self.parse_group::<T>()?;
+                    moves.extend(self.parse_repetition(group)?);
+                    self.check_length(moves.len(), start)?;
+                }
+                Some('[') => {
+                    let start = self.position;
+                    let group = self.parse_commutator::<T>()?;
+                    moves.extend(self.parse_repetition(group)?);
+                    self.check_length(moves.len(), start)?;
+                }
+                Some(c) if T::starts_with(c) => moves.push(T::parse(self)?),
+                Some(c) => return Err(self.error(ParseErrorKind::UnexpectedCharacter(c))),
+            }
+        }
+    }
+
+    /// Parses `( sequence )`.
+    fn parse_group<T: Token>(&mut self) -> Result<Vec<T>, ParseError> {
+        let start = self.enter()?;
+        let moves = self.parse_sequence()?;
+        self.expect_closing(')', start)?;
+        self.nesting -= 1;
+        Ok(moves)
+    }
+
+    /// Parses `[ a, b ]` (the commutator a b a' b') or `[ a: b ]` (the conjugate a b a').
+    fn parse_commutator<T: Token>(&mut self) -> Result<Vec<T>, ParseError> {
+        let start = self.enter()?;
+        let a = self.parse_sequence()?;
+        let commutator = match self.peek() {
+            Some(',') => true,
+            Some(':') => false,
+            Some(c) => return Err(self.error(ParseErrorKind::UnexpectedCharacter(c))),
+            None => {
+                return Err(ParseError {
+                    position: start,
+                    kind: ParseErrorKind::Unclosed('['),
+                })
+            }
+        };
+        self.position += 1;
+        let b = self.parse_sequence()?;
+        self.expect_closing(']', start)?;
+        self.nesting -= 1;
+
+        let inverse = |moves: &[T]| moves.iter().rev().map(|m| m.inverse()).collect::<Vec<_>>();
+        let mut moves = a.clone();
+        moves.extend(&b);
+        moves.extend(inverse(&a));
+        if commutator {
+            moves.extend(inverse(&b));
+        }
+        Ok(moves)
+    }
+
+    /// Steps over the opening bracket of a group or commutator and returns its position, or fails if
+    /// that nests too deeply.
+    fn enter(&mut self) -> Result<usize, ParseError> {
+        if self.nesting == MAX_NESTING {
+            return Err(self.error(ParseErrorKind::TooDeep));
+        }
+        self.nesting += 1;
+        let start = self.position;
+        self.position += 1;
+        Ok(start)
+    }
+
+    fn expect_closing(&mut self, closing: char, start: usize) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == closing => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(ParseErrorKind::UnexpectedCharacter(c))),
+            None => Err(ParseError {
+                position: start,
+                kind: ParseErrorKind::Unclosed(self.chars[start]),
+            }),
+        }
+    }
+
+    /// Parses an optional repetition count and an optional `'` after a group.
+    fn parse_repetition<T: Token>(&mut self, group: Vec<T>) -> Result<Vec<T>, ParseError> {
+        let start = self.position;
+        let count = self.parse_number()?.unwrap_or(1) as usize;
+        self.check_length(group.len().saturating_mul(count), start)?;
+        let group = if self.parse_prime() {
+            group.iter().rev().map(|m| m.inverse()).collect()
+        } else {
+            group
+        };
+        Ok(group.repeat(count))
+    }
+
+    /// Fails if `len` moves are more than a sequence can have, with the error at `position`.
+    fn check_length(&self, len: usize, position: usize) -> Result<(), ParseError> {
+        if len > MAX_MOVES {
+            return Err(ParseError {
+                position,
+                kind: ParseErrorKind::TooLong,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_number(&mut self) -> Result<Option<u32>, ParseError> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Ok(None);
+        }
+
+        let digits = self.chars[start..self.position].iter().collect::<String>();
+        digits.parse().map(Some).map_err(|_| ParseError {
+            position: start,
+            kind: ParseErrorKind::UnexpectedCharacter(self.chars[start]),
+        })
+    }
+
+    fn parse_prime(&mut self) -> bool {
+        // the typographic apostrophe shows up a lot when copying algorithms from websites
+        if matches!(self.peek(), Some('\'' | '’')) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses a single move with its amount, e.g. `R`, `Rw2` or `x'`.
+    fn parse_move(&mut self) -> Result<Move, ParseError> {
+        use Move::*;
+
+        let letter = self.peek().unwrap();
+        self.position += 1;
+        let wide = letter.is_ascii_uppercase() && self.peek() == Some('w');
+        if wide {
+            self.position += 1;
+        }
+
+        #[rustfmt::skip]
+        let [quarter, prime, half] = match (letter, wide) {
+            ('L', false) => [L, LPrime, L2],
+            ('R', false) => [R, RPrime, R2],
+            ('U', false) => [U, UPrime, U2],
+            ('D', false) => [D, DPrime, D2],
+            ('F', false) => [F, FPrime, F2],
+            ('B', false) => [B, BPrime, B2],
+            ('M', false) => [M, MPrime, M2],
+            ('E', false) => [E, EPrime, E2],
+            ('S', false) => [S, SPrime, S2],
+            ('L', true) | ('l', _) => [Lw, LwPrime, Lw2],
+            ('R', true) | ('r', _) => [Rw, RwPrime, Rw2],
+            ('U', true) | ('u', _) => [Uw, UwPrime, Uw2],
+            ('D', true) | ('d', _) => [Dw, DwPrime, Dw2],
+            ('F', true) | ('f', _) => [Fw, FwPrime, Fw2],
+            ('B', true) | ('b', _) => [Bw, BwPrime, Bw2],
+            ('x', _) => [X, XPrime, X2],
+            ('y', _) => [Y, YPrime, Y2],
+            ('z', _) => [Z, ZPrime, Z2],
+            (c, wide) => {
+                // slices have no wide form, so for `Mw` it's the `w` that doesn't belong there
+                self.position -= 1;
+                let unexpected = if wide { 'w' } else { c };
+                return Err(self.error(ParseErrorKind::UnexpectedCharacter(unexpected)));
+            }
+        };
+
+        self.parse_amount([quarter, prime, half])
+    }
+
+    /// Parses the amount after a move, and returns the quarter, inverse or half turn accordingly.
+    fn parse_amount(&mut self, [quarter, prime, half]: [Move; 3]) -> Result<Move, ParseError> {
+        let amount_at = self.position;
+        let amount = self.parse_number()?.unwrap_or(1);
+        if !(1..=3).contains(&amount) {
+            return Err(ParseError {
+                position: amount_at,
+                kind: ParseErrorKind::InvalidAmount(amount),
+            });
+        }
+
+        let quarter_turns = if self.parse_prime() {
+            4 - amount
+        } else {
+            amount
+        };
+        Ok([quarter, half, prime][quarter_turns as usize - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turn::Move::*;
+
+    fn parse(s: &str) -> Vec<Move> {
+        s.parse::<MoveSequence>().unwrap().0
+    }
+
+    fn error(s: &str) -> ParseError {
+        s.parse::<MoveSequence>().unwrap_err()
+    }
+
+    #[test]
+    fn single_moves() {
+        assert_eq!("R".parse(), Ok(R));
+        assert_eq!(" R' ".parse(), Ok(RPrime));
+        assert_eq!("Rw2".parse(), Ok(Rw2));
+        assert_eq!("r".parse(), Ok(Rw));
+        assert_eq!("x'".parse(), Ok(XPrime));
+        assert_eq!(
+            "R U".parse::<Move>(),
+            Err(ParseError {
+                position: 0,
+                kind: ParseErrorKind::NotASingleMove
+            })
+        );
+    }
+
+    #[test]
+    fn sequence() {
+        assert_eq!(parse("R U R' U'"), [R, U, RPrime, UPrime]);
+        assert_eq!(parse("RUR'U'"), [R, U, RPrime, UPrime]);
+        assert_eq!(
+            parse("R2 U2' M E' S2 x y2 z'"),
+            [R2, U2, M, EPrime, S2, X, Y2, ZPrime]
+        );
+        assert_eq!(parse("Rw r' Lw2 u f’"), [Rw, RwPrime, Lw2, Uw, FwPrime]);
+        assert_eq!(parse(""), []);
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(parse("(R U)3"), [R, U, R, U, R, U]);
+        assert_eq!(parse("(R U2)'"), [U2, RPrime]);
+        assert_eq!(parse("F (R U R' U') F'"), [F, R, U, RPrime, UPrime, FPrime]);
+        assert_eq!(parse("((R U)2 D)2").len(), 10);
+    }
+
+    #[test]
+    fn commutators() {
+        assert_eq!(parse("[R, U]"), [R, U, RPrime, UPrime]);
+        assert_eq!(parse("[F: R U]"), [F, R, U, FPrime]);
+        assert_eq!(
+            parse("[R U: [R, U]]"),
+            [R, U, R, U, RPrime, UPrime, UPrime, RPrime]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            parse("R U // first pair\nR' /* not U */ F"),
+            [R, U, RPrime, F]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let s = "R U' F2 M' Rw2 x y' z2 Dw'";
+        assert_eq!(s.parse::<MoveSequence>().unwrap().to_string(), s);
+    }
+
+    #[test]
+    fn nxn_moves() {
+        let parse = |s: &str| s.parse::<NxNSequence>().unwrap().0;
+
+        assert_eq!("2R".parse(), Ok(NxNMove::layers(R, 2, 2)));
+        assert_eq!("3Rw'".parse(), Ok(NxNMove::layers(RPrime, 1, 3)));
+        assert_eq!("2-3Uw2".parse(), Ok(NxNMove::layers(U2, 2, 3)));
+        assert_eq!("2-3U2".parse(), Ok(NxNMove::layers(U2, 2, 3)));
+        assert_eq!(
+            parse("R 1R 2Rw r M' x"),
+            [R, R, Rw, Rw, MPrime, X].map(NxNMove::Move)
+        );
+        assert_eq!(parse("(2L 3Fw)2'").len(), 4,);
+        assert_eq!(parse("[2R, U]")[2], NxNMove::layers(RPrime, 2, 2));
+
+        let s = "R 2U' 3Fw2 2-3Lw' M x Rw 4-4Dw";
+        assert_eq!(
+            s.parse::<NxNSequence>().unwrap().to_string(),
+            "R 2U' 3Fw2 2-3Lw' M x Rw 4D"
+        );
+
+        let error = |s: &str| s.parse::<NxNSequence>().unwrap_err();
+        assert_eq!(
+            error("R 0R"),
+            ParseError {
+                position: 2,
+                kind: ParseErrorKind::InvalidLayers
+            }
+        );
+        assert_eq!(error("3-2Rw").kind, ParseErrorKind::InvalidLayers);
+        assert_eq!(
+            error("3x"),
+            ParseError {
+                position: 1,
+                kind: ParseErrorKind::UnexpectedCharacter('x')
+            }
+        );
+        assert_eq!(
+            "2R".parse::<MoveSequence>().unwrap_err().kind,
+            ParseErrorKind::UnexpectedCharacter('2')
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            error("R U Q"),
+            ParseError {
+                position: 4,
+                kind: ParseErrorKind::UnexpectedCharacter('Q')
+            }
+        );
+        assert_eq!(
+            error("R Mw"),
+            ParseError {
+                position: 3,
+                kind: ParseErrorKind::UnexpectedCharacter('w')
+            }
+        );
+        assert_eq!(
+            error("R4"),
+            ParseError {
+                position: 1,
+                kind: ParseErrorKind::InvalidAmount(4)
+            }
+        );
+        assert_eq!(
+            error("R (U R"),
+            ParseError {
+                position: 2,
+                kind: ParseErrorKind::Unclosed('(')
+            }
+        );
+        assert_eq!(
+            error("R U)"),
+            ParseError {
+                position: 3,
+                kind: ParseErrorKind::UnexpectedCharacter(')')
+            }
+        );
+        assert_eq!(
+            error("[R U]"),
+            ParseError {
+                position: 4,
+                kind: ParseErrorKind::UnexpectedCharacter(']')
+            }
+        );
+        assert_eq!(
+            error("R /* U"),
+            ParseError {
+                position: 2,
+                kind: ParseErrorKind::Unclosed('/')
+            }
+        );
+        assert_eq!(
+            error("R U Q").to_string(),
+            "unexpected character 'Q' at position 4"
+        );
+    }
+
+    #[test]
+    fn too_long() {
+        let too_long = |position| ParseError {
+            position,
+            kind: ParseErrorKind::TooLong,
+        };
+        assert_eq!(error("(R U)4294967295"), too_long(5));
+        assert_eq!(error("((R U)1000)1000"), too_long(11));
+        assert_eq!(error("(R U)500000 (R U)500000"), too_long(12));
+
+        let longest = format!("(R){MAX_MOVES}'");
+        assert_eq!(longest.parse::<MoveSequence>().unwrap().0.len(), MAX_MOVES);
+    }
+
+    #[test]
+    fn too_deep() {
+        let too_deep = ParseError {
+            position: MAX_NESTING,
+            kind: ParseErrorKind::TooDeep,
+        };
+        assert_eq!(error(&"(".repeat(1_000_000)), too_deep);
+        assert_eq!(
+            error(&"[R: ".repeat(1_000_000)),
+            ParseError {
+                position: 4 * MAX_NESTING,
+                ..too_deep
+            }
+        );
+        let mixed = "([".repeat(MAX_NESTING / 2 + 1) + &"]".repeat(MAX_NESTING);
+        assert_eq!(error(&mixed), too_deep);
+
+        let deepest = "(".repeat(MAX_NESTING) + "R" + &")".repeat(MAX_NESTING);
+        assert_eq!(parse(&deepest), [R]);
+    }
+}
@@ -0,0 +1,762 @@
+//! Cubes of any size from 2x2 to 7x7.
+//!
+//! [`NxNCube`] stores every sticker separately, so it is a lot slower than [`Cube`] and [`CubieCube`],
+//! but it can be turned like any other [`Turnable`] and additionally by [`NxNMove`]s, which turn inner
+//! layers or any number of layers at once.
+//!
+//! [`CubieCube`]: crate::cubie::CubieCube
+
+use std::{collections::BTreeMap, fmt::Display, str::FromStr, sync::OnceLock};
+
+use crate::{
+    cube::{Color, Cube, FaceletStringError, FACELET_ORDER},
+    turn::{Move, Turnable},
+};
+
+/// A move on an [`NxNCube`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NxNMove {
+    /// A move that also exists on a 3x3, see [`NxNCube`] for what it turns on other sizes.
+    Move(Move),
+    /// Turns the layers `first..=last` the way `turn` turns its face, where the face itself is layer 1.
+    /// `turn` is one of the 18 face moves, so `3Rw'` is `Layers { turn: Move::RPrime, first: 1, last: 3 }`
+    /// and `2U2` is `Layers { turn: Move::U2, first: 2, last: 2 }`.
+    Layers { turn: Move, first: u8, last: u8 },
+}
+
+impl NxNMove {
+    /// The move that turns the layers `first..=last` like `turn`, which has to be a face move. Turns that
+    /// are the same as a 3x3 move, like `1R` or `1-2Rw`, are returned as that move.
+    ///
+    /// # Panic
+    /// This method panics if `turn` isn't a face move or `first..=last` isn't a range of layers.
+    pub fn layers(turn: Move, first: u8, last: u8) -> Self {
+        assert!((turn as u8) < Move::M as u8, "{turn} is not a face move");
+        assert!(1 <= first && first <= last, "invalid layers {first}-{last}");
+        match (first, last) {
+            (1, 1) => NxNMove::Move(turn),
+            (1, 2) => NxNMove::Move(WIDE[turn as usize]),
+            _ => NxNMove::Layers { turn, first, last },
+        }
+    }
+
+    /// Returns the move that undoes this move.
+    pub fn inverse(self) -> Self {
+        match self {
+            NxNMove::Move(m) => NxNMove::Move(m.inverse()),
+            NxNMove::Layers { turn, first, last } => NxNMove::Layers {
+                turn: turn.inverse(),
+                first,
+                last,
+            },
+        }
+    }
+
+    /// Whether this move can be made on a cube with `size` layers.
+    pub fn fits(self, size: usize) -> bool {
+        match self {
+            NxNMove::Move(_) => true,
+            NxNMove::Layers { last, .. } => last as usize <= size,
+        }
+    }
+
+    /// This move as a turn of the layers `first..=last` like a face move, on a cube with `size` layers.
+    /// Slice moves on a 2x2 don't turn any layers.
+    pub(crate) fn face_turn(self, size: usize) -> Option<(Move, usize, usize)> {
+        let m = match self {
+            NxNMove::Move(m) => m,
+            NxNMove::Layers { turn, first, last } => {
+                return Some((turn, first as usize, last as usize))
+            }
+        };
+        let amount = m as usize % 3;
+        Some(match m as usize / 3 {
+            face @ 0..6 => (FACE_MOVES[face][amount], 1, 1),
+            _ if size == 2 && m.layer().is_some() => return None,
+            // M turns like L, E like D and S like F
+            slice @ 6..9 => (FACE_MOVES[[0, 3, 4][slice - 6]][amount], 2, size - 1),
+            wide @ 9..15 => (FACE_MOVES[wide - 9][amount], 1, 2),
+            // x turns like R, y like U and z like F
+            rotation => (FACE_MOVES[[1, 2, 4][rotation - 15]][amount], 1, size),
+        })
+    }
+}
+
+impl From<Move> for NxNMove {
+    fn from(m: Move) -> Self {
+        NxNMove::Move(m)
+    }
+}
+
+/// The quarter, inverse and half turn of every face, in the order of [`Move`].
+#[rustfmt::skip]
+pub(crate) const FACE_MOVES: [[Move; 3]; 6] = {
+    use Move::*;
+    [
+        [L, LPrime, L2],
+        [R, RPrime, R2],
+        [U, UPrime, U2],
+        [D, DPrime, D2],
+        [F, FPrime, F2],
+        [B, BPrime, B2],
+    ]
+};
+
+/// The wide move with the same face and amount as each face move.
+#[rustfmt::skip]
+const WIDE: [Move; 18] = {
+    use Move::*;
+    [
+        Lw, LwPrime, Lw2,
+        Rw, RwPrime, Rw2,
+        Uw, UwPrime, Uw2,
+        Dw, DwPrime, Dw2,
+        Fw, FwPrime, Fw2,
+        Bw, BwPrime, Bw2,
+    ]
+};
+
+/// A cube with `size` layers along each axis, for sizes 2 to 7.
+///
+/// The stickers of a face are stored row by row, and the faces are laid out like the faces of a [`Cube`],
+/// so the first row of the top face is the one next to the back face and the first row of every side
+/// face is the one next to the top. A 3x3 `NxNCube` has exactly the stickers of the equivalent [`Cube`],
+/// and the facelet strings of both are the same: the stickers of the faces in U R F D L B order.
+///
+/// Moves that also exist on a 3x3 turn these layers:
+/// - face moves like `R` turn the outer layer
+/// - wide moves like `Rw` turn the outer two layers, as in WCA notation
+/// - slice moves like `M` turn every layer except the two outer ones, so they only turn the middle layer on
+///   odd cubes and nothing at all on a 2x2
+/// - rotations turn every layer
+///
+/// Cubes with an even size have no fixed centers, so the centers move around with inner layer turns and
+/// the color of a face isn't known until it is solved.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NxNCube {
+    size: usize,
+    stickers: Vec<Color>,
+}
+
+impl NxNCube {
+    /// The colors of the faces of a solved cube, in [`Cube::TOP`] to [`Cube::BACK`] order.
+    const COLORS: [Color; 6] = [
+        Color::White,
+        Color::Yellow,
+        Color::Orange,
+        Color::Red,
+        Color::Green,
+        Color::Blue,
+    ];
+
+    /// A solved cube with `size` layers.
+    ///
+    /// # Panic
+    /// This method panics if `size` is not in `2..=7`.
+    pub fn new(size: usize) -> Self {
+        assert!((2..=7).contains(&size), "unsupported cube size: {size}");
+        Self {
+            size,
+            stickers: Self::COLORS
+                .iter()
+                .flat_map(|&color| std::iter::repeat_n(color, size * size))
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The stickers of a face, row by row. Faces are numbered like [`Cube::TOP`] to [`Cube::BACK`].
+    pub fn face(&self, face: usize) -> &[Color] {
+        let area = self.size * self.size;
+        &self.stickers[face * area..(face + 1) * area]
+    }
+
+    /// # Panic
+    /// This method panics if `face` is not in `0..6` or `row` and `column` are not in `0..size`.
+    pub fn get(&self, face: usize, row: usize, column: usize) -> Color {
+        self.stickers[self.index(face, row, column)]
+    }
+
+    /// # Panic
+    /// This method panics if `face` is not in `0..6` or `row` and `column` are not in `0..size`.
+    pub fn set(&mut self, face: usize, row: usize, column: usize, color: Color) {
+        let index = self.index(face, row, column);
+        self.stickers[index] = color;
+    }
+
+    fn index(&self, face: usize, row: usize, column: usize) -> usize {
+        assert!(
+            face < 6 && row < self.size && column < self.size,
+            "sticker out of range: {face} {row} {column}"
+        );
+        (face * self.size + row) * self.size + column
+    }
+
+    /// Returns whether every face has a single color, no matter how the cube is oriented.
+    pub fn is_solved(&self) -> bool {
+        (0..6).all(|face| {
+            let stickers = self.face(face);
+            stickers.iter().all(|&color| color == stickers[0])
+        })
+    }
+
+    /// Turns the cube by `m`.
+    ///
+    /// # Panic
+    /// This method panics if `m` turns layers the cube doesn't have, see [`NxNMove::fits`].
+    pub fn turn(&mut self, m: NxNMove) {
+        assert!(
+            m.fits(self.size),
+            "{m} doesn't fit on a {0}x{0} cube",
+            self.size
+        );
+        if let Some((turn, first, last)) = m.face_turn(self.size) {
+            self.turn_face(turn, first, last);
+        }
+    }
+
+    pub fn turn_all(&mut self, moves: &[NxNMove]) {
+        for &m in moves {
+            self.turn(m);
+        }
+    }
+
+    /// Where `moves` move every sticker of a cube with `size` layers: the sticker at index `i` (counting
+    /// row by row and face by face) ends up at index `result[i]`.
+    ///
+    /// # Panic
+    /// This method panics if a move doesn't fit on the cube, see [`NxNMove::fits`].
+    pub(crate) fn permutation(size: usize, moves: &[NxNMove]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..6 * size * size).collect();
+        for &m in moves {
+            assert!(m.fits(size), "{m} doesn't fit on a {size}x{size} cube");
+            if let Some((turn, first, last)) = m.face_turn(size) {
+                turn_face(size, &mut indices, turn, first, last);
+            }
+        }
+
+        let mut result = vec![0; indices.len()];
+        for (to, from) in indices.into_iter().enumerate() {
+            result[from] = to;
+        }
+        result
+    }
+
+    /// The stickers of every piece of a cube with `size` layers, as indices like in
+    /// [`NxNCube::permutation`]. Centers have one sticker, edges two and corners three, and pieces are
+    /// ordered by their first sticker.
+    pub(crate) fn pieces(size: usize) -> Vec<Vec<usize>> {
+        let n = size as i32;
+        let mut pieces = BTreeMap::<[i32; 3], Vec<usize>>::new();
+        for index in 0..6 * size * size {
+            let (face, row, column) = (index / (size * size), index / size % size, index % size);
+            // the piece a sticker belongs to is just below the surface
+            let piece = position(n, face, row as i32, column as i32).map(|v| v.clamp(1 - n, n - 1));
+            pieces.entry(piece).or_default().push(index);
+        }
+        let mut pieces: Vec<_> = pieces.into_values().collect();
+        pieces.sort();
+        pieces
+    }
+
+    fn turn_face(&mut self, turn: Move, first: usize, last: usize) {
+        turn_face(self.size, &mut self.stickers, turn, first, last);
+    }
+}
+
+/// Turns the layers `first..=last` (counted from the face, starting at 1) of a cube with `size` layers
+/// like the face move `turn`, where `items` are the stickers of the cube.
+fn turn_face<T: Copy>(size: usize, items: &mut [T], turn: Move, first: usize, last: usize) {
+    let quarter_turns = [1, 3, 2][turn as usize % 3];
+    // L, D and B are turned clockwise when looking at the negative side of their axis
+    let (axis, positive) = [
+        (0, false),
+        (0, true),
+        (1, true),
+        (1, false),
+        (2, true),
+        (2, false),
+    ][turn as usize / 3];
+    let (layers, quarter_turns) = if positive {
+        (size - last..=size - first, quarter_turns)
+    } else {
+        (first - 1..=last - 1, 4 - quarter_turns)
+    };
+    turn_layers(size, items, axis, layers, quarter_turns);
+}
+
+/// Turns the given layers of an axis clockwise (looking at the positive side) `quarter_turns` times. The
+/// axes go from left to right, bottom to top and back to front, and the layers are numbered from the
+/// negative side.
+fn turn_layers<T: Copy>(
+    size: usize,
+    items: &mut [T],
+    axis: usize,
+    layers: std::ops::RangeInclusive<usize>,
+    quarter_turns: usize,
+) {
+    let turns = layer_turns(size);
+    for layer in layers {
+        for _ in 0..quarter_turns % 4 {
+            let old = items.to_vec();
+            for &(from, to) in &turns[axis * size + layer] {
+                items[to as usize] = old[from as usize];
+            }
+        }
+    }
+}
+
+impl Default for NxNCube {
+    /// A solved 3x3.
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+/// The stickers a turn moves, as `(from, to)` pairs of indices.
+type Permutation = Vec<(u16, u16)>;
+
+/// The permutations of clockwise quarter turns of every layer of a cube with `size` layers, indexed by
+/// `axis * size + layer`, see [`turn_layers`].
+fn layer_turns(size: usize) -> &'static [Permutation] {
+    static TURNS: [OnceLock<Vec<Permutation>>; 6] = [const { OnceLock::new() }; 6];
+    TURNS[size - 2].get_or_init(|| {
+        let n = size as i32;
+        let area = size * size;
+        let mut turns = vec![Vec::new(); 3 * size];
+        for index in 0..6 * area {
+            let (face, row, column) = (index / area, index % area / size, index % size);
+            let position = position(n, face, row as i32, column as i32);
+            for (axis, &value) in position.iter().enumerate() {
+                let layer = ((value.clamp(1 - n, n - 1) + n - 1) / 2) as usize;
+                let [x, y, z] = position;
+                let turned = match axis {
+                    0 => [x, z, -y],
+                    1 => [-z, y, x],
+                    _ => [y, -x, z],
+                };
+                let (face, row, column) = sticker(n, turned);
+                let to = (face * size + row as usize) * size + column as usize;
+                turns[axis * size + layer].push((index as u16, to as u16));
+            }
+        }
+        turns
+    })
+}
+
+/// The position of a sticker, in coordinates from `-n` to `n` where the stickers of a face are two apart
+/// and the faces are at `±n`. The axes are the ones of [`turn_layers`].
+fn position(n: i32, face: usize, row: i32, column: i32) -> [i32; 3] {
+    let (r, c) = (2 * row - (n - 1), 2 * column - (n - 1));
+    match face {
+        Cube::TOP => [c, n, r],
+        Cube::BOTTOM => [c, -n, -r],
+        Cube::LEFT => [-n, -r, c],
+        Cube::RIGHT => [n, -r, -c],
+        Cube::FRONT => [c, -r, n],
+        _ => [-c, -r, -n],
+    }
+}
+
+/// The reverse of [`position`].
+fn sticker(n: i32, [x, y, z]: [i32; 3]) -> (usize, i32, i32) {
+    let (face, r, c) = if y == n {
+        (Cube::TOP, z, x)
+    } else if y == -n {
+        (Cube::BOTTOM, -z, x)
+    } else if x == -n {
+        (Cube::LEFT, -y, z)
+    } else if x == n {
+        (Cube::RIGHT, -y, -z)
+    } else if z == n {
+        (Cube::FRONT, -y, x)
+    } else {
+        (Cube::BACK, -y, -x)
+    };
+    (face, (r + n - 1) / 2, (c + n - 1) / 2)
+}
+
+impl Turnable for NxNCube {
+    fn r(&mut self) {
+        self.turn_face(Move::R, 1, 1);
+    }
+
+    fn rprime(&mut self) {
+        self.turn_face(Move::RPrime, 1, 1);
+    }
+
+    fn l(&mut self) {
+        self.turn_face(Move::L, 1, 1);
+    }
+
+    fn lprime(&mut self) {
+        self.turn_face(Move::LPrime, 1, 1);
+    }
+
+    fn u(&mut self) {
+        self.turn_face(Move::U, 1, 1);
+    }
+
+    fn uprime(&mut self) {
+        self.turn_face(Move::UPrime, 1, 1);
+    }
+
+    fn d(&mut self) {
+        self.turn_face(Move::D, 1, 1);
+    }
+
+    fn dprime(&mut self) {
+        self.turn_face(Move::DPrime, 1, 1);
+    }
+
+    fn f(&mut self) {
+        self.turn_face(Move::F, 1, 1);
+    }
+
+    fn fprime(&mut self) {
+        self.turn_face(Move::FPrime, 1, 1);
+    }
+
+    fn b(&mut self) {
+        self.turn_face(Move::B, 1, 1);
+    }
+
+    fn bprime(&mut self) {
+        self.turn_face(Move::BPrime, 1, 1);
+    }
+
+    fn m(&mut self) {
+        if self.size > 2 {
+            self.turn_face(Move::L, 2, self.size - 1);
+        }
+    }
+
+    fn mprime(&mut self) {
+        if self.size > 2 {
+            self.turn_face(Move::LPrime, 2, self.size - 1);
+        }
+    }
+
+    fn e(&mut self) {
+        if self.size > 2 {
+            self.turn_face(Move::D, 2, self.size - 1);
+        }
+    }
+
+    fn eprime(&mut self) {
+        if self.size > 2 {
+            self.turn_face(Move::DPrime, 2, self.size - 1);
+        }
+    }
+
+    fn s(&mut self) {
+        if self.size > 2 {
+            self.turn_face(Move::F, 2, self.size - 1);
+        }
+    }
+
+    fn sprime(&mut self) {
+        if self.size > 2 {
+            self.turn_face(Move::FPrime, 2, self.size - 1);
+        }
+    }
+
+    fn rw(&mut self) {
+        self.turn_face(Move::R, 1, 2);
+    }
+
+    fn rwprime(&mut self) {
+        self.turn_face(Move::RPrime, 1, 2);
+    }
+
+    fn lw(&mut self) {
+        self.turn_face(Move::L, 1, 2);
+    }
+
+    fn lwprime(&mut self) {
+        self.turn_face(Move::LPrime, 1, 2);
+    }
+
+    fn uw(&mut self) {
+        self.turn_face(Move::U, 1, 2);
+    }
+
+    fn uwprime(&mut self) {
+        self.turn_face(Move::UPrime, 1, 2);
+    }
+
+    fn dw(&mut self) {
+        self.turn_face(Move::D, 1, 2);
+    }
+
+    fn dwprime(&mut self) {
+        self.turn_face(Move::DPrime, 1, 2);
+    }
+
+    fn fw(&mut self) {
+        self.turn_face(Move::F, 1, 2);
+    }
+
+    fn fwprime(&mut self) {
+        self.turn_face(Move::FPrime, 1, 2);
+    }
+
+    fn bw(&mut self) {
+        self.turn_face(Move::B, 1, 2);
+    }
+
+    fn bwprime(&mut self) {
+        self.turn_face(Move::BPrime, 1, 2);
+    }
+
+    fn x(&mut self) {
+        self.turn_face(Move::R, 1, self.size);
+    }
+
+    fn xprime(&mut self) {
+        self.turn_face(Move::RPrime, 1, self.size);
+    }
+
+    fn y(&mut self) {
+        self.turn_face(Move::U, 1, self.size);
+    }
+
+    fn yprime(&mut self) {
+        self.turn_face(Move::UPrime, 1, self.size);
+    }
+
+    fn z(&mut self) {
+        self.turn_face(Move::F, 1, self.size);
+    }
+
+    fn zprime(&mut self) {
+        self.turn_face(Move::FPrime, 1, self.size);
+    }
+}
+
+impl From<Cube> for NxNCube {
+    fn from(cube: Cube) -> Self {
+        Self {
+            size: 3,
+            stickers: (0..6).flat_map(|face| cube.face(face).colors()).collect(),
+        }
+    }
+}
+
+impl TryFrom<NxNCube> for Cube {
+    type Error = NxNCube;
+
+    /// Converts a 3x3, or returns the cube back if it has another size.
+    fn try_from(cube: NxNCube) -> Result<Self, Self::Error> {
+        if cube.size != 3 {
+            return Err(cube);
+        }
+
+        let mut result = Cube::default();
+        for face in 0..6 {
+            for (index, &color) in cube.face(face).iter().enumerate() {
+                result.face_mut(face).set(index, color);
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Display for NxNCube {
+    /// Writes the facelet string of this cube, see [`NxNCube`] for the format.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (face, _) in FACELET_ORDER {
+            for color in self.face(face) {
+                let (_, letter) = FACELET_ORDER
+                    .iter()
+                    .find(|&&(face, _)| Self::COLORS[face] == *color)
+                    .unwrap();
+                write!(f, "{letter}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NxNCube {
+    type Err = FaceletStringError;
+
+    /// Reads a facelet string, see [`NxNCube`] for the format. The size is taken from the length of the
+    /// string, and the result isn't checked to be a cube that can be solved.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let count = s.chars().count();
+        let size = (2..=7)
+            .find(|size| 6 * size * size == count)
+            .ok_or(FaceletStringError::Size(count))?;
+
+        let mut cube = NxNCube::new(size);
+        let area = size * size;
+        for (position, letter) in s.chars().enumerate() {
+            let (face, _) = FACELET_ORDER
+                .iter()
+                .find(|&&(_, l)| l == letter)
+                .ok_or(FaceletStringError::InvalidCharacter { position, letter })?;
+            let (target, _) = FACELET_ORDER[position / area];
+            cube.stickers[target * area + position % area] = Self::COLORS[*face];
+        }
+        Ok(cube)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::NxNSequence;
+
+    fn parse(s: &str) -> Vec<NxNMove> {
+        s.parse::<NxNSequence>().unwrap().0
+    }
+
+    fn turned(size: usize, moves: &str) -> NxNCube {
+        let mut cube = NxNCube::new(size);
+        cube.turn_all(&parse(moves));
+        cube
+    }
+
+    #[test]
+    fn same_as_3x3() {
+        let mut cube = Cube::default();
+        let mut nxn = NxNCube::new(3);
+        assert_eq!(NxNCube::from(cube), nxn);
+
+        let moves = "R U' F2 L D B' M E' S2 Rw Lw' Uw2 Dw Fw' Bw x y' z2";
+        for m in moves.parse::<crate::MoveSequence>().unwrap().0 {
+            cube.perform(m);
+            nxn.perform(m);
+            assert_eq!(NxNCube::from(cube), nxn, "after {m}");
+        }
+        assert_eq!(cube.to_string(), nxn.to_string());
+        assert_eq!(Cube::try_from(nxn), Ok(cube));
+        assert!(Cube::try_from(NxNCube::new(4)).is_err());
+    }
+
+    #[test]
+    fn inverse() {
+        let moves = parse("R 2U' 3Fw2 2-3Lw M D' x Bw 3Rw' 2-2B");
+        let inverse: Vec<_> = moves.iter().rev().map(|m| m.inverse()).collect();
+        for size in 2..=7 {
+            let moves: Vec<_> = moves.iter().copied().filter(|m| m.fits(size)).collect();
+            let mut cube = NxNCube::new(size);
+            cube.turn_all(&moves);
+            assert!(!cube.is_solved(), "{size}x{size}");
+            cube.turn_all(
+                &inverse
+                    .iter()
+                    .copied()
+                    .filter(|m| m.fits(size))
+                    .collect::<Vec<_>>(),
+            );
+            assert_eq!(cube, NxNCube::new(size), "{size}x{size}");
+        }
+    }
+
+    #[test]
+    fn layers() {
+        // wide moves are the outer two layers, slice moves everything between the outer layers
+        assert_eq!(turned(4, "Rw"), turned(4, "R 2R"));
+        assert_eq!(turned(5, "3Rw"), turned(5, "R 2R 3R"));
+        assert_eq!(turned(5, "M"), turned(5, "2-4Lw"));
+        assert_eq!(turned(5, "M"), turned(5, "4L 2L 3L"));
+        assert_eq!(turned(4, "E'"), turned(4, "2U 3U"));
+        assert_eq!(turned(2, "M S E"), NxNCube::new(2));
+
+        // rotations turn everything
+        assert_eq!(turned(6, "x"), turned(6, "3Rw 4R Lw'"));
+        assert_eq!(turned(7, "y'"), turned(7, "7Uw'"));
+        assert_eq!(turned(4, "z2"), turned(4, "4Bw2"));
+        assert!(turned(5, "x y z").is_solved());
+        assert!(!turned(5, "x y z").to_string().starts_with("UUU"));
+    }
+
+    #[test]
+    fn moving_centers() {
+        // turning both halves of a 4x4 is a rotation, which leaves the centers on other faces
+        let mut cube = turned(4, "Rw");
+        assert!(!cube.is_solved());
+        cube.perform(Move::LwPrime);
+        assert!(cube.is_solved());
+        assert_ne!(cube, NxNCube::new(4));
+        assert_eq!(cube, turned(4, "x"));
+
+        // on a 3x3 the centers never leave their face, on a 4x4 they do
+        let cube = turned(4, "2R U 2R'");
+        let centers = [(1, 1), (1, 2), (2, 1), (2, 2)];
+        assert!(centers
+            .iter()
+            .any(|&(row, column)| cube.get(Cube::TOP, row, column) != Color::White));
+        assert_eq!(turned(3, "M U M'").face(Cube::TOP)[4], Color::White);
+    }
+
+    #[test]
+    fn order() {
+        for size in 2..=7 {
+            for m in parse("R 2R 3Lw' 2-3F2 M Uw x") {
+                if !m.fits(size) {
+                    continue;
+                }
+                let mut cube = NxNCube::new(size);
+                cube.turn(m);
+                if size > 2 || m != NxNMove::Move(Move::M) {
+                    assert!(cube != NxNCube::new(size), "{m} on {size}x{size}");
+                }
+                cube.turn_all(&[m, m, m]);
+                assert_eq!(cube, NxNCube::new(size), "{m} on {size}x{size}");
+            }
+        }
+    }
+
+    #[test]
+    fn pieces() {
+        for size in 2..=7 {
+            let pieces = NxNCube::pieces(size);
+            let count = |stickers| pieces.iter().filter(|p| p.len() == stickers).count();
+            assert_eq!(count(3), 8);
+            assert_eq!(count(2), 12 * (size - 2));
+            assert_eq!(count(1), 6 * (size - 2) * (size - 2));
+        }
+        assert_eq!(NxNCube::pieces(4)[0], [0, 32, 83]);
+
+        // every move keeps the stickers of a piece together
+        let pieces = NxNCube::pieces(5);
+        let permutation = NxNCube::permutation(5, &parse("R 2U' 3Fw x M"));
+        for piece in &pieces {
+            let mut moved: Vec<_> = piece.iter().map(|&i| permutation[i]).collect();
+            moved.sort();
+            assert!(pieces.contains(&moved), "{piece:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic = "doesn't fit"]
+    fn too_deep() {
+        NxNCube::new(3).turn(NxNMove::layers(Move::R, 2, 4));
+    }
+
+    #[test]
+    fn facelet_string() {
+        assert_eq!(NxNCube::new(2).to_string(), "UUUURRRRFFFFDDDDLLLLBBBB");
+        for size in 2..=7 {
+            let cube = turned(size, "R U 2F' Lw2 2Dw'");
+            assert_eq!(cube.to_string().parse(), Ok(cube));
+        }
+        let facelets = turned(4, "R").to_string();
+        assert_eq!(&facelets[..16], "UUUFUUUFUUUFUUUF");
+
+        assert_eq!("UUUU".parse::<NxNCube>(), Err(FaceletStringError::Size(4)));
+        assert_eq!(
+            "UUUURRRRFFFFDDDDLLLLBBBW".parse::<NxNCube>(),
+            Err(FaceletStringError::InvalidCharacter {
+                position: 23,
+                letter: 'W'
+            })
+        );
+    }
+}
@@ -0,0 +1,662 @@
+//! Optimal solving of 2x2 rubiks cubes (pocket cubes).
+//!
+//! A 2x2 has only 3,674,160 positions once its orientation is ignored, so [`PocketSolver`] stores the
+//! distance of every one of them and finds optimal solutions by simply following the table downwards.
+
+use rayon::prelude::*;
+
+use crate::{
+    cube::{Cube, ValidationError},
+    cubie::{
+        permutation_rank, permutation_unrank, to_move, CubieCube, FaceletError, CORNER_FACELETS,
+        FACE_TURNS,
+    },
+    nxn::NxNCube,
+    turn::{Metric, Move, Turnable},
+};
+
+/// The corner that never moves when only the U, R and F faces are turned, which is DBL.
+const FIXED: usize = 6;
+/// The number of positions of the seven corners other than [`FIXED`] (7!).
+const PERMUTATIONS: usize = 5040;
+/// The number of orientations of the seven corners other than [`FIXED`] (3^6).
+const TWISTS: usize = 729;
+/// The U, R and F turns, which are the first nine of [`FACE_TURNS`].
+const MOVES: usize = 9;
+
+/// A 2x2 rubiks cube, stored like the corners of a [`CubieCube`]: `cp[i]` is the corner at position `i`
+/// and `co[i]` the number of clockwise twists it has.
+///
+/// Without centers there is nothing that decides the orientation of a 2x2, so whole cube rotations move
+/// the corners like any other move and every one of the 24 orientations of the solved cube counts as
+/// solved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PocketCube {
+    pub cp: [u8; 8],
+    pub co: [u8; 8],
+}
+
+impl PocketCube {
+    pub const SOLVED: Self = Self {
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        co: [0; 8],
+    };
+
+    /// Returns the cube you get by first applying `self` and then `other`.
+    pub fn multiply(&self, other: &Self) -> Self {
+        Self::from_cubies(&self.cubies().multiply(&other.cubies()))
+    }
+
+    /// Returns the cube that undoes `self`.
+    pub fn inverse(&self) -> Self {
+        Self::from_cubies(&self.cubies().inverse())
+    }
+
+    fn cubies(&self) -> CubieCube {
+        CubieCube {
+            cp: self.cp,
+            co: self.co,
+            ..CubieCube::SOLVED
+        }
+    }
+
+    fn from_cubies(cubies: &CubieCube) -> Self {
+        Self {
+            cp: cubies.cp,
+            co: cubies.co,
+        }
+    }
+
+    /// All 24 orientations of the solved cube.
+    fn rotations() -> Vec<Self> {
+        let mut rotations = vec![Self::SOLVED];
+        let mut i = 0;
+        while i < rotations.len() {
+            for rotation in [Move::X, Move::Y] {
+                let mut rotated = rotations[i];
+                rotated.perform(rotation);
+                if !rotations.contains(&rotated) {
+                    rotations.push(rotated);
+                }
+            }
+            i += 1;
+        }
+        rotations
+    }
+
+    /// Returns whether every face has a single color, no matter how the cube is oriented.
+    pub fn is_solved(&self) -> bool {
+        Self::rotations().contains(self)
+    }
+
+    /// Checks that this cube can be solved by turning it, i.e. that its corners haven't been twisted or
+    /// duplicated.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for position in 0..8 {
+            let corner = self.cp[position];
+            if corner > 7 || self.co[position] > 2 {
+                return Err(FaceletError::InvalidCorner(position).into());
+            }
+            if self.cp[..position].contains(&corner) {
+                return Err(FaceletError::DuplicateCorner(corner as usize).into());
+            }
+        }
+        if !self.co.iter().sum::<u8>().is_multiple_of(3) {
+            return Err(ValidationError::CornerTwist);
+        }
+        Ok(())
+    }
+
+    /// The same cube, rotated so that [`FIXED`] is solved. Turning only U, R and F from there solves
+    /// `self` in some orientation.
+    fn normalized(&self) -> Self {
+        Self::rotations()
+            .into_iter()
+            .find(|rotation| {
+                rotation.cp[FIXED] == self.cp[FIXED] && rotation.co[FIXED] == self.co[FIXED]
+            })
+            .unwrap()
+            .inverse()
+            .multiply(self)
+    }
+
+    /// The index of a normalized cube in the distance table of [`PocketSolver`].
+    fn index(&self) -> usize {
+        permutation(self) as usize * TWISTS + twist(self) as usize
+    }
+
+    fn turn(&mut self, m: usize) {
+        *self = self.multiply(&Self::from_cubies(&FACE_TURNS[m]));
+    }
+}
+
+/// The positions of the corners other than [`FIXED`] in `0..7!`, for a cube where it is solved.
+fn permutation(cube: &PocketCube) -> u16 {
+    let mut others = [0; 7];
+    for (i, position) in (0..8).filter(|&p| p != FIXED).enumerate() {
+        let corner = cube.cp[position];
+        others[i] = if corner as usize > FIXED {
+            corner - 1
+        } else {
+            corner
+        };
+    }
+    permutation_rank(&others) as u16
+}
+
+fn set_permutation(cube: &mut PocketCube, rank: u16) {
+    let mut others = [0; 7];
+    permutation_unrank(rank as u32, &mut others);
+    for (i, position) in (0..8).filter(|&p| p != FIXED).enumerate() {
+        let corner = others[i];
+        cube.cp[position] = if corner as usize >= FIXED {
+            corner + 1
+        } else {
+            corner
+        };
+    }
+    cube.cp[FIXED] = FIXED as u8;
+}
+
+/// The orientations of the corners other than [`FIXED`] in `0..3^6`, for a cube where it is solved. The
+/// last corner's orientation follows from the others.
+fn twist(cube: &PocketCube) -> u16 {
+    cube.co[..FIXED]
+        .iter()
+        .fold(0, |acc, &o| acc * 3 + o as u16)
+}
+
+fn set_twist(cube: &mut PocketCube, mut twist: u16) {
+    let mut sum = 0;
+    for i in (0..FIXED).rev() {
+        cube.co[i] = (twist % 3) as u8;
+        sum += cube.co[i];
+        twist /= 3;
+    }
+    cube.co[FIXED] = 0;
+    cube.co[7] = (3 - sum % 3) % 3;
+}
+
+impl Turnable for PocketCube {
+    fn r(&mut self) {
+        self.turn(3);
+    }
+
+    fn rprime(&mut self) {
+        self.turn(5);
+    }
+
+    fn l(&mut self) {
+        self.turn(12);
+    }
+
+    fn lprime(&mut self) {
+        self.turn(14);
+    }
+
+    fn u(&mut self) {
+        self.turn(0);
+    }
+
+    fn uprime(&mut self) {
+        self.turn(2);
+    }
+
+    fn d(&mut self) {
+        self.turn(9);
+    }
+
+    fn dprime(&mut self) {
+        self.turn(11);
+    }
+
+    fn f(&mut self) {
+        self.turn(6);
+    }
+
+    fn fprime(&mut self) {
+        self.turn(8);
+    }
+
+    fn b(&mut self) {
+        self.turn(15);
+    }
+
+    fn bprime(&mut self) {
+        self.turn(17);
+    }
+
+    // a 2x2 has no slices, so slice moves don't do anything and wide moves turn the whole cube
+
+    fn m(&mut self) {}
+
+    fn mprime(&mut self) {}
+
+    fn e(&mut self) {}
+
+    fn eprime(&mut self) {}
+
+    fn s(&mut self) {}
+
+    fn sprime(&mut self) {}
+
+    fn rw(&mut self) {
+        self.x();
+    }
+
+    fn rwprime(&mut self) {
+        self.xprime();
+    }
+
+    fn lw(&mut self) {
+        self.xprime();
+    }
+
+    fn lwprime(&mut self) {
+        self.x();
+    }
+
+    fn uw(&mut self) {
+        self.y();
+    }
+
+    fn uwprime(&mut self) {
+        self.yprime();
+    }
+
+    fn dw(&mut self) {
+        self.yprime();
+    }
+
+    fn dwprime(&mut self) {
+        self.y();
+    }
+
+    fn fw(&mut self) {
+        self.z();
+    }
+
+    fn fwprime(&mut self) {
+        self.zprime();
+    }
+
+    fn bw(&mut self) {
+        self.zprime();
+    }
+
+    fn bwprime(&mut self) {
+        self.z();
+    }
+
+    fn x(&mut self) {
+        self.r();
+        self.lprime();
+    }
+
+    fn xprime(&mut self) {
+        self.rprime();
+        self.l();
+    }
+
+    fn y(&mut self) {
+        self.u();
+        self.dprime();
+    }
+
+    fn yprime(&mut self) {
+        self.uprime();
+        self.d();
+    }
+
+    fn z(&mut self) {
+        self.f();
+        self.bprime();
+    }
+
+    fn zprime(&mut self) {
+        self.fprime();
+        self.b();
+    }
+}
+
+/// The sticker of a 3x3 facelet `(face, index)` on the corresponding corner of a cube with `size` layers.
+fn corner_sticker(size: usize, (face, index): (usize, usize)) -> (usize, usize, usize) {
+    let far = |i| if i == 0 { 0 } else { size - 1 };
+    (face, far(index / 3), far(index % 3))
+}
+
+impl TryFrom<&NxNCube> for PocketCube {
+    type Error = FaceletError;
+
+    /// Reads the corners of a cube of any size, which for a 2x2 is the whole cube. The faces are told apart
+    /// by the colors they have when solved, since a 2x2 has no centers to go by.
+    fn try_from(cube: &NxNCube) -> Result<Self, Self::Error> {
+        let solved = NxNCube::new(2);
+        let face_of = |facelet| {
+            let (face, row, column) = corner_sticker(cube.size(), facelet);
+            let color = cube.get(face, row, column);
+            (0..6)
+                .find(|&face| solved.get(face, 0, 0) == color)
+                .unwrap()
+        };
+
+        let mut result = Self::SOLVED;
+        for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let invalid = FaceletError::InvalidCorner(position);
+            let faces = facelets.map(face_of);
+            let orientation = faces
+                .iter()
+                .position(|&face| face == Cube::TOP || face == Cube::BOTTOM)
+                .ok_or(invalid)?;
+            let clockwise = [0, 1, 2].map(|i| faces[(orientation + i) % 3]);
+            let corner = CORNER_FACELETS
+                .iter()
+                .position(|reference| reference.map(|(face, _)| face) == clockwise)
+                .ok_or(invalid)?;
+
+            if result.cp[..position].contains(&(corner as u8)) {
+                return Err(FaceletError::DuplicateCorner(corner));
+            }
+            result.cp[position] = corner as u8;
+            result.co[position] = orientation as u8;
+        }
+        Ok(result)
+    }
+}
+
+impl From<PocketCube> for NxNCube {
+    /// Paints the stickers of every corner on a 2x2.
+    fn from(pocket: PocketCube) -> Self {
+        let solved = NxNCube::new(2);
+        let color = |facelet| {
+            let (face, row, column) = corner_sticker(2, facelet);
+            solved.get(face, row, column)
+        };
+
+        let mut cube = solved.clone();
+        for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let corner = CORNER_FACELETS[pocket.cp[position] as usize];
+            for (i, &facelet) in facelets.iter().enumerate() {
+                let from = (i + 3 - pocket.co[position] as usize) % 3;
+                let (face, row, column) = corner_sticker(2, facelet);
+                cube.set(face, row, column, color(corner[from]));
+            }
+        }
+        cube
+    }
+}
+
+/// An optimal 2x2 solver that knows the distance of every position.
+///
+/// The table has one byte for each of the 3,674,160 positions and is built once, in well under a second,
+/// when the solver is created. After that, every solution is found by repeatedly making a move that gets
+/// one step closer to solved, which takes a few microseconds.
+///
+/// Solutions only turn the U, R and F faces, which is enough because turning the opposite face is the
+/// same as turning this one and rotating the cube. They may leave the cube solved in another orientation.
+#[derive(Debug)]
+pub struct PocketSolver {
+    metric: Metric,
+    /// The moves of the metric, as indices into [`FACE_TURNS`].
+    moves: Vec<usize>,
+    permutation_moves: Vec<[u16; MOVES]>,
+    twist_moves: Vec<[u16; MOVES]>,
+    distances: Vec<u8>,
+    distribution: Vec<u64>,
+}
+
+impl PocketSolver {
+    /// A solver that counts moves in the half turn metric.
+    pub fn new() -> Self {
+        Self::with_metric(Metric::Half)
+    }
+
+    /// A solver that counts moves in `metric`. Slice turns don't do anything on a 2x2, so
+    /// [`Metric::Slice`] is the same as [`Metric::Half`].
+    pub fn with_metric(metric: Metric) -> Self {
+        let moves = match metric {
+            Metric::Quarter => vec![0, 2, 3, 5, 6, 8],
+            Metric::Half | Metric::Slice => (0..MOVES).collect(),
+        };
+        let turns = FACE_TURNS.map(|m| PocketCube::from_cubies(&m));
+
+        let permutation_moves = (0..PERMUTATIONS as u16)
+            .map(|rank| {
+                let mut cube = PocketCube::SOLVED;
+                set_permutation(&mut cube, rank);
+                std::array::from_fn(|m| permutation(&cube.multiply(&turns[m])))
+            })
+            .collect::<Vec<_>>();
+        let twist_moves = (0..TWISTS as u16)
+            .map(|rank| {
+                let mut cube = PocketCube::SOLVED;
+                set_twist(&mut cube, rank);
+                std::array::from_fn(|m| twist(&cube.multiply(&turns[m])))
+            })
+            .collect::<Vec<_>>();
+
+        let mut solver = Self {
+            metric,
+            moves,
+            permutation_moves,
+            twist_moves,
+            distances: Vec::new(),
+            distribution: Vec::new(),
+        };
+        solver.fill_distances();
+        solver
+    }
+
+    /// Breadth first search over the whole state space, one layer at a time.
+    fn fill_distances(&mut self) {
+        const UNKNOWN: u8 = u8::MAX;
+
+        let mut distances = vec![UNKNOWN; PERMUTATIONS * TWISTS];
+        distances[PocketCube::SOLVED.index()] = 0;
+        let mut distribution = vec![1];
+        let mut depth = 0;
+        while *distribution.last().unwrap() > 0 {
+            let mut found = 0;
+            for index in 0..distances.len() {
+                if distances[index] != depth {
+                    continue;
+                }
+                for &m in &self.moves {
+                    let next = self.apply(index, m);
+                    if distances[next] == UNKNOWN {
+                        distances[next] = depth + 1;
+                        found += 1;
+                    }
+                }
+            }
+            distribution.push(found);
+            depth += 1;
+        }
+        distribution.pop();
+
+        self.distances = distances;
+        self.distribution = distribution;
+    }
+
+    fn apply(&self, index: usize, m: usize) -> usize {
+        let permutation = self.permutation_moves[index / TWISTS][m] as usize;
+        let twist = self.twist_moves[index % TWISTS][m] as usize;
+        permutation * TWISTS + twist
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The number of moves an optimal solution of `cube` has.
+    pub fn distance(&self, cube: PocketCube) -> Result<u8, ValidationError> {
+        cube.validate()?;
+        Ok(self.distances[cube.normalized().index()])
+    }
+
+    /// Finds an optimal solution of `cube`, or returns an error if it can't be solved at all.
+    pub fn solve(&self, cube: PocketCube) -> Result<Vec<Move>, ValidationError> {
+        cube.validate()?;
+        let mut index = cube.normalized().index();
+        let mut solution = Vec::new();
+        while self.distances[index] > 0 {
+            let (m, next) = self
+                .moves
+                .iter()
+                .map(|&m| (m, self.apply(index, m)))
+                .find(|&(_, next)| self.distances[next] < self.distances[index])
+                .expect("every position has a neighbor that is closer to solved");
+            solution.push(to_move(m));
+            index = next;
+        }
+        Ok(solution)
+    }
+
+    /// Like [`PocketSolver::solve`], but solves all `cubes` in parallel on rayon's global thread pool. The
+    /// solutions are in the same order as the cubes.
+    pub fn solve_batch(&self, cubes: &[PocketCube]) -> Vec<Result<Vec<Move>, ValidationError>> {
+        cubes.par_iter().map(|&cube| self.solve(cube)).collect()
+    }
+
+    /// How many positions there are at each distance from solved, starting with the solved cube itself.
+    /// The last distance is God's number, the most moves any position needs.
+    pub fn distribution(&self) -> &[u64] {
+        &self.distribution
+    }
+}
+
+impl Default for PocketSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{nxn::NxNMove, MoveSequence};
+
+    fn scrambled(moves: &str) -> PocketCube {
+        let mut cube = PocketCube::SOLVED;
+        cube.perform_all(&moves.parse::<MoveSequence>().unwrap().0);
+        cube
+    }
+
+    #[test]
+    fn same_as_stickers() {
+        let moves = "R U' F2 L D B' M Rw Lw' Uw2 Dw Fw' Bw x y' z2";
+        let mut cube = PocketCube::SOLVED;
+        let mut stickers = NxNCube::new(2);
+        for m in moves.parse::<MoveSequence>().unwrap().0 {
+            cube.perform(m);
+            stickers.perform(m);
+            assert_eq!(NxNCube::from(cube), stickers, "after {m}");
+            assert_eq!(PocketCube::try_from(&stickers), Ok(cube), "after {m}");
+        }
+
+        // the corners of bigger cubes turn the same way
+        let mut big = NxNCube::new(5);
+        big.turn_all(&[NxNMove::Move(Move::R), NxNMove::layers(Move::U, 2, 3)]);
+        assert_eq!(PocketCube::try_from(&big), Ok(scrambled("R")));
+    }
+
+    #[test]
+    fn solved_in_any_orientation() {
+        assert!(PocketCube::SOLVED.is_solved());
+        assert!(scrambled("x y2 z'").is_solved());
+        assert!(scrambled("R L'").is_solved());
+        assert!(!scrambled("R").is_solved());
+        assert_eq!(PocketCube::rotations().len(), 24);
+    }
+
+    #[test]
+    fn coordinates_round_trip() {
+        let mut cube = PocketCube::SOLVED;
+        set_permutation(&mut cube, 1234);
+        set_twist(&mut cube, 567);
+        assert_eq!(cube.cp[FIXED], FIXED as u8);
+        assert_eq!(permutation(&cube), 1234);
+        assert_eq!(twist(&cube), 567);
+        assert_eq!(cube.validate(), Ok(()));
+    }
+
+    #[test]
+    fn distribution() {
+        let solver = PocketSolver::new();
+        assert_eq!(
+            solver.distribution(),
+            [1, 9, 54, 321, 1847, 9992, 50136, 227536, 870072, 1887748, 623800, 2644]
+        );
+        assert_eq!(
+            solver.distribution().iter().sum::<u64>(),
+            (PERMUTATIONS * TWISTS) as u64
+        );
+
+        let solver = PocketSolver::with_metric(Metric::Quarter);
+        assert_eq!(
+            solver.distribution(),
+            [
+                1, 6, 27, 120, 534, 2256, 8969, 33058, 114149, 360508, 930588, 1350852, 782536,
+                90280, 276
+            ]
+        );
+    }
+
+    #[test]
+    fn optimal_solutions() {
+        let solver = PocketSolver::new();
+        assert_eq!(solver.solve(PocketCube::SOLVED), Ok(vec![]));
+        assert_eq!(solver.distance(scrambled("R U")), Ok(2));
+        assert_eq!(solver.distance(scrambled("L D")), Ok(2));
+        assert_eq!(solver.distance(scrambled("x R2 y")), Ok(1));
+        assert_eq!(solver.distance(scrambled("R U R' U'")), Ok(4));
+
+        let mut rng = StdRng::seed_from_u64(2);
+        let moves = Metric::Half.moves();
+        let cubes: Vec<_> = (0..100)
+            .map(|_| {
+                let mut cube = PocketCube::SOLVED;
+                for _ in 0..30 {
+                    cube.perform(moves[rng.random_range(0..moves.len())]);
+                }
+                cube.perform([Move::X, Move::Y, Move::ZPrime][rng.random_range(0..3)]);
+                cube
+            })
+            .collect();
+        for (&cube, solution) in cubes.iter().zip(solver.solve_batch(&cubes)) {
+            let solution = solution.unwrap();
+            assert_eq!(Ok(solution.len() as u8), solver.distance(cube));
+            assert!(solution.len() <= 11);
+
+            let mut cube = cube;
+            cube.perform_all(&solution);
+            assert!(cube.is_solved());
+        }
+    }
+
+    #[test]
+    fn quarter_turns() {
+        let solver = PocketSolver::with_metric(Metric::Quarter);
+        let cube = scrambled("R2 U2");
+        assert_eq!(solver.distance(cube), Ok(4));
+        let solution = solver.solve(cube).unwrap();
+        assert!(solution.iter().all(|m| Metric::Quarter.moves().contains(m)));
+    }
+
+    #[test]
+    fn unsolvable() {
+        let solver = PocketSolver::new();
+        let mut cube = PocketCube::SOLVED;
+        cube.co[0] = 1;
+        assert_eq!(solver.solve(cube), Err(ValidationError::CornerTwist));
+
+        let mut cube = PocketCube::SOLVED;
+        cube.cp[1] = 0;
+        assert_eq!(
+            solver.solve(cube),
+            Err(ValidationError::Facelets(FaceletError::DuplicateCorner(0)))
+        );
+    }
+}
@@ -0,0 +1,555 @@
+//! Solving 4x4 rubiks cubes with the reduction method.
+//!
+//! [`ReductionSolver`] first solves the centers and pairs up the edges, after which the 4x4 can be turned
+//! like a 3x3 with its outer layers. That 3x3 is then solved by a [`TwoPhaseSolver`], after fixing the two
+//! parities a 3x3 can't have.
+//!
+//! The 3x3 stage doesn't use the optimal [`crate::Solver`]: a reduced 4x4 is a random 3x3 position, which
+//! usually needs about 18 moves, far beyond what its breadth first search can do. The two-phase solution
+//! isn't optimal, and only depends on the time the search gets if it doesn't find one of at most 20
+//! moves before the [timeout](ReductionSolver::timeout).
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use crate::{
+    cube::{Color, Cube, ValidationError},
+    cubie::FaceletError,
+    notation::NxNSequence,
+    nxn::{NxNCube, NxNMove, FACE_MOVES},
+    pocket::PocketCube,
+    turn::Move,
+    two_phase::TwoPhaseSolver,
+};
+
+/// Cycles three centers without changing anything else.
+const CENTER_CYCLE: &str = "2R U 2L' U' 2R' U 2L U'";
+/// Cycles three edge wings without changing anything else.
+const WING_CYCLE: &str = "2R U R' U' 2R' U R U'";
+/// Swaps the two wings of the UF edge, which flips that edge on the reduced 3x3. It also swaps centers
+/// of the top face with each other, which doesn't change their colors.
+const OLL_PARITY: &str = "2R2 B2 U2 2L U2 2R' U2 2R U2 F2 2R F2 2L' B2 2R2";
+/// Swaps the UF and UB edges of the reduced 3x3, and some corners and centers of the same color.
+const PLL_PARITY: &str = "2R2 U2 2R2 Uw2 2R2 Uw2";
+
+/// Sequences that cycle three pieces of one kind, for any three of them.
+#[derive(Debug)]
+struct Cycles {
+    /// The stickers of each piece.
+    pieces: Vec<Vec<usize>>,
+    /// The moves that move piece `a` to the position of piece `b`, `b` to `c` and `c` to `a`, indexed by
+    /// `[a, b, c]`.
+    sequences: HashMap<[usize; 3], Vec<NxNMove>>,
+}
+
+impl Cycles {
+    /// Finds the cycles of the pieces with `stickers` stickers by conjugating `commutator`, a cycle of
+    /// three of them, with the shortest setup that moves the three pieces into the places it cycles.
+    fn new(stickers: usize, commutator: &str) -> Self {
+        let pieces: Vec<_> = NxNCube::pieces(4)
+            .into_iter()
+            .filter(|piece| piece.len() == stickers)
+            .collect();
+        let mut piece_at = vec![usize::MAX; 96];
+        for (i, piece) in pieces.iter().enumerate() {
+            for &sticker in piece {
+                piece_at[sticker] = i;
+            }
+        }
+        // where every piece goes with each move
+        let destinations = |moves: &[NxNMove]| -> Vec<usize> {
+            let permutation = NxNCube::permutation(4, moves);
+            pieces
+                .iter()
+                .map(|piece| piece_at[permutation[piece[0]]])
+                .collect()
+        };
+
+        // the pieces in the places that the commutator and its inverse cycle
+        let commutator = sequence(commutator);
+        let commutators = [inverse(&commutator), commutator];
+        let mut queue = VecDeque::new();
+        let mut setups = HashMap::new();
+        for (i, moves) in commutators.iter().enumerate() {
+            let next = destinations(moves);
+            let a = (0..pieces.len())
+                .find(|&piece| next[piece] != piece)
+                .unwrap();
+            let (b, c) = (next[a], next[next[a]]);
+            for cycle in [[a, b, c], [b, c, a], [c, a, b]] {
+                setups.insert(cycle, (Vec::new(), i));
+                queue.push_back(cycle);
+            }
+        }
+
+        // a turn before a setup moves the pieces from the places where the turn takes them, so a breadth
+        // first search finds the shortest setup for every three pieces
+        let turns: Vec<_> = turns()
+            .map(|turn| (turn, destinations(&[turn.inverse()])))
+            .collect();
+        while let Some(cycle) = queue.pop_front() {
+            for (turn, destinations) in &turns {
+                let from = cycle.map(|piece| destinations[piece]);
+                if !setups.contains_key(&from) {
+                    let (setup, i) = &setups[&cycle];
+                    let mut longer = vec![*turn];
+                    longer.extend_from_slice(setup);
+                    setups.insert(from, (longer, *i));
+                    queue.push_back(from);
+                }
+            }
+        }
+
+        let sequences = setups
+            .into_iter()
+            .map(|(cycle, (setup, i))| {
+                let mut moves = setup.clone();
+                moves.extend_from_slice(&commutators[i]);
+                moves.extend(inverse(&setup));
+                (cycle, moves)
+            })
+            .collect();
+        Self { pieces, sequences }
+    }
+
+    /// The piece one of whose stickers is `sticker`.
+    fn piece(&self, sticker: usize) -> Option<usize> {
+        self.pieces
+            .iter()
+            .position(|piece| piece.contains(&sticker))
+    }
+}
+
+fn sequence(moves: &str) -> Vec<NxNMove> {
+    moves.parse::<NxNSequence>().unwrap().0
+}
+
+fn inverse(moves: &[NxNMove]) -> Vec<NxNMove> {
+    moves.iter().rev().map(|m| m.inverse()).collect()
+}
+
+/// The layers a move turns, as the face it turns like and the range of layers from that face.
+fn layer(m: NxNMove) -> (usize, usize, usize) {
+    let (turn, first, last) = m.face_turn(4).unwrap();
+    (turn as usize / 3, first, last)
+}
+
+/// Every turn of a single layer of a 4x4.
+fn turns() -> impl Iterator<Item = NxNMove> {
+    // every layer of each axis from the R, U and F side, and the outer layer of the other side
+    [Move::R, Move::U, Move::F]
+        .into_iter()
+        .flat_map(|face| {
+            (1..=3).flat_map(move |layer| {
+                FACE_MOVES[face as usize / 3].map(|turn| NxNMove::layers(turn, layer, layer))
+            })
+        })
+        .chain(
+            [Move::L, Move::D, Move::B]
+                .into_iter()
+                .flat_map(|face| FACE_MOVES[face as usize / 3].map(NxNMove::Move)),
+        )
+}
+
+/// Merges consecutive turns of the same layers and drops the ones that cancel out.
+fn simplify(moves: &[NxNMove]) -> Vec<NxNMove> {
+    let quarter_turns = |m: NxNMove| [1, 3, 2][m.face_turn(4).unwrap().0 as usize % 3];
+
+    let mut result: Vec<NxNMove> = Vec::new();
+    for &m in moves {
+        let merged = result
+            .last()
+            .filter(|&&previous| layer(previous) == layer(m))
+            .map(|&previous| (quarter_turns(previous) + quarter_turns(m)) % 4);
+        match merged {
+            None => result.push(m),
+            Some(0) => {
+                result.pop();
+            }
+            Some(quarter_turns) => {
+                let (face, first, last) = layer(m);
+                let turn = FACE_MOVES[face][[0, 0, 2, 1][quarter_turns]];
+                *result.last_mut().unwrap() = NxNMove::layers(turn, first as u8, last as u8);
+            }
+        }
+    }
+    result
+}
+
+/// A 4x4 solver using the reduction method, which is how most people solve bigger cubes.
+///
+/// The centers are solved first, into the color scheme of the default orientation of [`Cube`], and then
+/// every edge wing is put into its place, which pairs up the edges. Both steps move three pieces at a time
+/// with commutators, so the pieces they have already placed stay where they are. Then the outer layers are
+/// solved like a 3x3 by a [`TwoPhaseSolver`] rather than the optimal [`crate::Solver`], which would take far
+/// too long for most positions. The two-phase solver stops at the first 3x3 solution of at most 20 moves,
+/// which usually takes a few tens of milliseconds, or at the shortest one it found once the
+/// [timeout](ReductionSolver::timeout) of 100 milliseconds has passed. So the 3x3 part of the solution is
+/// not optimal, and only when the timeout is reached does it depend on how fast the machine is.
+///
+/// A 4x4 can end up in two states that no 3x3 can be in, which are fixed with the usual algorithms before
+/// solving the 3x3: OLL parity, where a single edge is flipped, and PLL parity, where two edges are swapped.
+///
+/// The solutions are far from the shortest ones, at several hundred moves, but every step is made of short
+/// algorithms.
+#[derive(Debug)]
+pub struct ReductionSolver {
+    solver: TwoPhaseSolver,
+    timeout: Duration,
+    centers: Cycles,
+    wings: Cycles,
+    /// The wing in each position, by the colors of the stickers of the position.
+    wing_colors: HashMap<(usize, Color, Color), usize>,
+}
+
+impl ReductionSolver {
+    /// Builds the solver along with a [`TwoPhaseSolver`], which takes a few seconds.
+    pub fn new() -> Self {
+        Self::with_solver(TwoPhaseSolver::new())
+    }
+
+    /// Like [`ReductionSolver::new`], but with a 3x3 solver that has already been built.
+    pub fn with_solver(solver: TwoPhaseSolver) -> Self {
+        let centers = Cycles::new(1, CENTER_CYCLE);
+        let wings = Cycles::new(2, WING_CYCLE);
+
+        // a wing fits into each position only one way around, so the colors in a position tell which wing
+        // is there
+        let solved = NxNCube::new(4);
+        let mut wing_colors = HashMap::new();
+        for (wing, stickers) in wings.pieces.iter().enumerate() {
+            for position in 0..wings.pieces.len() {
+                let mut cube = solved.clone();
+                if position != wing {
+                    let third = (0..)
+                        .find(|&third| third != wing && third != position)
+                        .unwrap();
+                    cube.turn_all(&wings.sequences[&[wing, position, third]]);
+                }
+                let [a, b] = [0, 1].map(|i| color(&cube, wings.pieces[position][i]));
+                assert!(stickers.iter().any(|&sticker| color(&solved, sticker) == a));
+                wing_colors.insert((position, a, b), wing);
+            }
+        }
+
+        Self {
+            solver,
+            timeout: Duration::from_millis(100),
+            centers,
+            wings,
+            wing_colors,
+        }
+    }
+
+    /// How long the 3x3 solver may look for a solution of at most 20 moves before it settles for the
+    /// shortest one it found. Defaults to 100 milliseconds.
+    ///
+    /// A solution of at most 20 moves is nearly always found well before that, and then the solution
+    /// is the same no matter how fast the search is. A shorter timeout can make the 3x3 part longer, and
+    /// different from run to run.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Finds a solution of `cube`, or returns an error if it can't be solved at all.
+    ///
+    /// # Panic
+    /// This method panics if `cube` isn't a 4x4.
+    pub fn solve(&self, cube: &NxNCube) -> Result<Vec<NxNMove>, ValidationError> {
+        assert_eq!(cube.size(), 4, "the reduction solver only solves 4x4s");
+        self.validate(cube)?;
+
+        let mut cube = cube.clone();
+        let mut solution = Vec::new();
+        let mut perform = |cube: &mut NxNCube, moves: &[NxNMove]| {
+            cube.turn_all(moves);
+            solution.extend_from_slice(moves);
+        };
+
+        self.solve_centers(&mut cube, &mut perform);
+        self.solve_wings(&mut cube, &mut perform);
+        loop {
+            let parity = match reduced(&cube).validate() {
+                Ok(()) => break,
+                Err(ValidationError::EdgeFlip) => OLL_PARITY,
+                Err(ValidationError::Parity) => PLL_PARITY,
+                Err(error) => unreachable!("the reduced cube is invalid: {error}"),
+            };
+            perform(&mut cube, &sequence(parity));
+        }
+
+        let moves = self
+            .solver
+            .first_solution(reduced(&cube), 20, self.timeout)
+            .expect("the reduced cube is valid");
+        solution.extend(moves.into_iter().map(NxNMove::Move));
+        Ok(simplify(&solution))
+    }
+
+    /// Checks that `cube` has a valid set of corners, every edge wing once and 4 centers of each color.
+    /// Unlike on a 3x3, any permutation of these pieces can be solved.
+    fn validate(&self, cube: &NxNCube) -> Result<(), ValidationError> {
+        PocketCube::try_from(cube)?.validate()?;
+
+        let mut found = [false; 24];
+        for position in 0..24 {
+            let wing = self
+                .wing_at(cube, position)
+                .ok_or(FaceletError::InvalidEdge(position))?;
+            if found[wing] {
+                return Err(FaceletError::DuplicateEdge(wing).into());
+            }
+            found[wing] = true;
+        }
+
+        let solved = NxNCube::new(4);
+        for face in 0..6 {
+            let color = solved.get(face, 0, 0);
+            let count = self
+                .centers
+                .pieces
+                .iter()
+                .filter(|piece| self::color(cube, piece[0]) == color)
+                .count();
+            if count != 4 {
+                return Err(ValidationError::CenterCount { color, count });
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts every center on the face with its color, one after another. A center is moved into place
+    /// together with another one that isn't solved yet, or one of the same color if there is none left.
+    fn solve_centers(
+        &self,
+        cube: &mut NxNCube,
+        perform: &mut impl FnMut(&mut NxNCube, &[NxNMove]),
+    ) {
+        let solved = NxNCube::new(4);
+        let positions = &self.centers.pieces;
+        let target = |position: usize| color(&solved, positions[position][0]);
+        for position in 0..positions.len() {
+            let current = |cube: &NxNCube, position: usize| color(cube, positions[position][0]);
+            let unsolved = |cube: &NxNCube, other: usize| current(cube, other) != target(other);
+            if !unsolved(cube, position) {
+                continue;
+            }
+
+            // everything before this position is solved, so the center it needs is somewhere after it
+            let from = (position + 1..positions.len())
+                .find(|&from| unsolved(cube, from) && current(cube, from) == target(position))
+                .unwrap();
+            let third = (position + 1..positions.len())
+                .find(|&third| third != from && unsolved(cube, third))
+                .or_else(|| {
+                    // the two centers are swapped, so cycle them with a center of the same color as one
+                    (0..positions.len()).find(|&third| {
+                        third != position && third != from && target(third) == target(from)
+                    })
+                })
+                .unwrap();
+            perform(cube, &self.centers.sequences[&[from, position, third]]);
+        }
+    }
+
+    /// Puts every edge wing into its place, which also pairs them up. The two wings of the UF edge come
+    /// last, since they are swapped with OLL parity if only they are left.
+    fn solve_wings(&self, cube: &mut NxNCube, perform: &mut impl FnMut(&mut NxNCube, &[NxNMove])) {
+        let last = [13, 14].map(|sticker| self.wings.piece(sticker).unwrap());
+        let order: Vec<_> = (0..24)
+            .filter(|position| !last.contains(position))
+            .chain(last.iter().copied())
+            .collect();
+
+        for (i, &position) in order.iter().enumerate() {
+            if self.wing_at(cube, position) == Some(position) {
+                continue;
+            }
+
+            let from = (0..24)
+                .find(|&from| self.wing_at(cube, from) == Some(position))
+                .unwrap();
+            let later = || {
+                order[i + 1..]
+                    .iter()
+                    .copied()
+                    .filter(|&third| third != from)
+            };
+            let third = later()
+                .find(|&third| self.wing_at(cube, third) != Some(third))
+                .or_else(|| later().next());
+            let Some(third) = third else {
+                break;
+            };
+            perform(cube, &self.wings.sequences[&[from, position, third]]);
+        }
+    }
+
+    /// The wing in `position`, or `None` if its stickers don't belong to any wing.
+    fn wing_at(&self, cube: &NxNCube, position: usize) -> Option<usize> {
+        let [a, b] = [0, 1].map(|i| color(cube, self.wings.pieces[position][i]));
+        self.wing_colors.get(&(position, a, b)).copied()
+    }
+}
+
+impl Default for ReductionSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The color of a sticker, by its index like in [`NxNCube::permutation`].
+fn color(cube: &NxNCube, sticker: usize) -> Color {
+    cube.face(sticker / 16)[sticker % 16]
+}
+
+/// The 3x3 that a 4x4 is reduced to, made of its corners, the first wing of every edge and a center of
+/// every face.
+fn reduced(cube: &NxNCube) -> Cube {
+    let mut result = NxNCube::new(3);
+    for face in 0..6 {
+        for row in 0..3 {
+            for column in 0..3 {
+                let layer = |i: usize| [0, 1, 3][i];
+                result.set(face, row, column, cube.get(face, layer(row), layer(column)));
+            }
+        }
+    }
+    Cube::try_from(result).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{MoveSequence, Turnable};
+
+    fn solver() -> &'static ReductionSolver {
+        static SOLVER: OnceLock<ReductionSolver> = OnceLock::new();
+        SOLVER.get_or_init(ReductionSolver::new)
+    }
+
+    fn scrambled(moves: &str) -> NxNCube {
+        let mut cube = NxNCube::new(4);
+        cube.turn_all(&sequence(moves));
+        cube
+    }
+
+    fn assert_solves(cube: &NxNCube) -> Vec<NxNMove> {
+        let solution = solver().solve(cube).unwrap();
+        let mut cube = cube.clone();
+        cube.turn_all(&solution);
+        assert_eq!(cube, NxNCube::new(4), "{}", NxNSequence(solution));
+        solution
+    }
+
+    #[test]
+    fn cycles() {
+        let solver = solver();
+        for cycles in [&solver.centers, &solver.wings] {
+            assert_eq!(cycles.pieces.len(), 24);
+            assert_eq!(cycles.sequences.len(), 24 * 23 * 22);
+            for (&[a, b, c], moves) in cycles.sequences.iter().step_by(97) {
+                let permutation = NxNCube::permutation(4, moves);
+                for (i, piece) in cycles.pieces.iter().enumerate() {
+                    let to = cycles.piece(permutation[piece[0]]).unwrap();
+                    let expected = [(a, b), (b, c), (c, a)]
+                        .into_iter()
+                        .find(|&(from, _)| from == i)
+                        .map_or(i, |(_, to)| to);
+                    assert_eq!(to, expected, "{}", NxNSequence(moves.clone()));
+                }
+            }
+        }
+        assert_eq!(solver.wing_colors.len(), 24 * 24);
+    }
+
+    #[test]
+    fn already_solved() {
+        assert_eq!(solver().solve(&NxNCube::new(4)), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn parity() {
+        // a single inner slice turn leaves an odd permutation of wings, and this one has both parities
+        assert_solves(&scrambled("2R"));
+        assert_solves(&scrambled(OLL_PARITY));
+        assert_solves(&scrambled(PLL_PARITY));
+        assert_solves(&scrambled(
+            "Rw U2 x Rw U2 Rw U2 Rw' U2 Lw U2 Rw' U2 Rw U2 Rw' U2 Rw'",
+        ));
+    }
+
+    #[test]
+    fn random_scrambles() {
+        let turns: Vec<NxNMove> = FACE_MOVES
+            .iter()
+            .flatten()
+            .flat_map(|&turn| [NxNMove::Move(turn), NxNMove::layers(turn, 1, 2)])
+            .collect();
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..5 {
+            let scramble: Vec<_> = (0..40)
+                .map(|_| turns[rng.random_range(0..turns.len())])
+                .collect();
+            let mut cube = NxNCube::new(4);
+            cube.turn_all(&scramble);
+            let solution = assert_solves(&cube);
+
+            // merged moves never turn the same layers twice in a row
+            for pair in solution.windows(2) {
+                assert_ne!(layer(pair[0]), layer(pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn reduced() {
+        // outer layer turns turn the reduced cube like a 3x3
+        let cube = scrambled("R U' F2 L D B' R2");
+        let mut expected = Cube::default();
+        expected.perform_all(&"R U' F2 L D B' R2".parse::<MoveSequence>().unwrap().0);
+        assert_eq!(super::reduced(&cube), expected);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut twisted = NxNCube::new(4);
+        twisted.set(Cube::TOP, 0, 0, Color::Blue);
+        twisted.set(Cube::BACK, 0, 3, Color::Orange);
+        twisted.set(Cube::LEFT, 0, 0, Color::White);
+        assert_eq!(solver().solve(&twisted), Err(ValidationError::CornerTwist));
+
+        // a wing flipped in place looks like the other wing of its edge
+        let mut flipped = NxNCube::new(4);
+        flipped.set(Cube::TOP, 3, 1, Color::Green);
+        flipped.set(Cube::FRONT, 0, 1, Color::White);
+        assert!(matches!(
+            solver().solve(&flipped),
+            Err(ValidationError::Facelets(FaceletError::DuplicateEdge(_)))
+        ));
+
+        let mut invalid = NxNCube::new(4);
+        invalid.set(Cube::FRONT, 0, 1, Color::Yellow);
+        assert!(matches!(
+            solver().solve(&invalid),
+            Err(ValidationError::Facelets(FaceletError::InvalidEdge(_)))
+        ));
+
+        let mut centers = NxNCube::new(4);
+        centers.set(Cube::TOP, 1, 1, Color::Yellow);
+        assert_eq!(
+            solver().solve(&centers),
+            Err(ValidationError::CenterCount {
+                color: Color::White,
+                count: 3
+            })
+        );
+    }
+}
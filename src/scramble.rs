@@ -0,0 +1,451 @@
+//! Scrambles for practice and competitions.
+//!
+//! A random state scramble (see [`RandomStateScrambler`]) gives every solvable position the same
+//! chance, like the official WCA scrambles. It picks a random position with [`random_state`] and
+//! returns the inverse of a solution, so applying the scramble to a solved cube leads to that position.
+//!
+//! A random move scramble (see [`RandomMoveScrambler`]) just turns random layers. It needs no tables
+//! and can be limited to a few layers, e.g. to practice ⟨R, U⟩ positions, but not every position is
+//! equally likely.
+
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    cube::Cube,
+    cubie::{odd_permutation, CubieCube},
+    solver::Solver,
+    turn::{Metric, Move},
+    two_phase::TwoPhaseSolver,
+};
+
+/// No position needs more moves than this in the half turn metric.
+const MAX_DISTANCE: u8 = 20;
+
+/// A position picked uniformly at random from all solvable positions.
+pub fn random_state(rng: &mut impl Rng) -> Cube {
+    let mut cubies = CubieCube::SOLVED;
+    cubies.cp.shuffle(rng);
+    cubies.ep.shuffle(rng);
+    // swapping two edges pairs up the permutations of each parity, so they stay equally likely
+    if odd_permutation(&cubies.cp) != odd_permutation(&cubies.ep) {
+        cubies.ep.swap(0, 1);
+    }
+    cubies.set_twist(rng.random_range(0..2187));
+    cubies.set_flip(rng.random_range(0..2048));
+    cubies.into()
+}
+
+/// Generates random state scrambles by solving random positions with a [`TwoPhaseSolver`].
+///
+/// Positions that are closer to the solved cube than the [minimum
+/// length](RandomStateScrambler::min_length) are found with an optimal [`Solver`] and skipped.
+///
+/// The solver takes a few seconds to build its tables, so a scrambler should be reused.
+///
+/// ```no_run
+/// use cubers::scramble::RandomStateScrambler;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let scrambler = RandomStateScrambler::new().min_length(2);
+/// let scramble = scrambler.scramble(&mut StdRng::seed_from_u64(42));
+/// assert!(scramble.len() >= 2);
+/// ```
+#[derive(Debug)]
+pub struct RandomStateScrambler {
+    solver: TwoPhaseSolver,
+    /// Finds the positions that are closer than `min_length`.
+    optimal: Solver,
+    min_length: u8,
+    max_length: u8,
+    timeout: Duration,
+}
+
+impl RandomStateScrambler {
+    /// Creates a scrambler for scrambles of at most 21 moves in the half turn metric.
+    pub fn new() -> Self {
+        Self::with_solver(TwoPhaseSolver::new())
+    }
+
+    /// Like [`RandomStateScrambler::new`], but with a solver that has already been built.
+    pub fn with_solver(solver: TwoPhaseSolver) -> Self {
+        Self {
+            solver,
+            optimal: Solver::with_metric(Metric::Half),
+            min_length: 0,
+            max_length: 21,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Skips positions that are solved in fewer than `moves` moves, so scrambles are at least that
+    /// long. The WCA skips positions that are less than 2 moves away from the solved cube.
+    ///
+    /// Every position is searched optimally up to `moves - 1` moves deep, which is quick for up to
+    /// about 8 moves but gets much slower beyond that, and impractical above about 10.
+    ///
+    /// # Panic
+    /// This method panics if `moves` is more than 20, the most moves any position needs, or more than the
+    /// [maximum length](RandomStateScrambler::max_length), because then no scramble would ever be long
+    /// enough.
+    pub fn min_length(mut self, moves: u8) -> Self {
+        assert!(
+            moves <= self.max_length.min(MAX_DISTANCE),
+            "a minimum length of {moves} is more than the maximum of {}",
+            self.max_length.min(MAX_DISTANCE)
+        );
+        self.min_length = moves;
+        self
+    }
+
+    /// Stops looking for a shorter scramble once one has at most `moves` moves.
+    ///
+    /// # Panic
+    /// This method panics if `moves` is less than the [minimum
+    /// length](RandomStateScrambler::min_length).
+    pub fn max_length(mut self, moves: u8) -> Self {
+        assert!(
+            moves >= self.min_length,
+            "a maximum length of {moves} is less than the minimum of {}",
+            self.min_length
+        );
+        self.max_length = moves;
+        self
+    }
+
+    /// How long the solver may look for a scramble of at most [the maximum
+    /// length](RandomStateScrambler::max_length), before it settles for the shortest one it found.
+    ///
+    /// Scrambles only depend on the random number generator as long as the solver finds one that is
+    /// short enough in time, which it practically always does with the default of 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// A scramble that leads to a random position when applied to a solved cube.
+    pub fn scramble(&self, rng: &mut impl Rng) -> Vec<Move> {
+        loop {
+            if let Some(scramble) = self.scramble_to(random_state(rng)) {
+                return scramble;
+            }
+        }
+    }
+
+    /// A scramble that leads to `cube`, or `None` if `cube` is closer to the solved cube than the
+    /// minimum length.
+    fn scramble_to(&self, cube: Cube) -> Option<Vec<Move>> {
+        let solution = self
+            .solver
+            .first_solution(cube, self.max_length, self.timeout)
+            .expect("random states are solvable");
+        // the two-phase solution is only an upper bound of the distance
+        if solution.len() < self.min_length as usize || self.closer_than_min_length(cube) {
+            return None;
+        }
+        Some(solution.iter().rev().map(|m| m.inverse()).collect())
+    }
+
+    fn closer_than_min_length(&self, cube: Cube) -> bool {
+        let Some(max_length) = self.min_length.checked_sub(1) else {
+            return false;
+        };
+        // both fronts of the search grow, so it finds every solution up to twice the depth
+        let depth = max_length.div_ceil(2);
+        let shortest = self
+            .optimal
+            .solve(cube, depth)
+            .expect("random states are solvable");
+        shortest.is_some_and(|solution| solution.len() <= max_length as usize)
+    }
+}
+
+impl Default for RandomStateScrambler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The reason a set of moves can't be used by a [`RandomMoveScrambler`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveSetError {
+    /// Only face and slice moves can be scrambled with, not wide moves or rotations.
+    NotALayerTurn(Move),
+    /// The moves don't turn layers of at least two axes, so scrambles would get stuck: after R L, both
+    /// R and L would be redundant.
+    SingleAxis,
+}
+
+impl Display for MoveSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveSetError::NotALayerTurn(m) => write!(f, "{m:?} is not a face or slice move"),
+            MoveSetError::SingleAxis => {
+                write!(f, "the moves have to turn around at least two axes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveSetError {}
+
+/// Generates scrambles of random moves, where no move can be merged with or cancelled by the ones
+/// before it: a layer is never turned again until a layer of another axis has been turned, like in
+/// R R2, R L R or R M2 L R.
+///
+/// ```
+/// use cubers::{scramble::RandomMoveScrambler, Move};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// // practice scrambles that only turn the right and top face
+/// let scrambler = RandomMoveScrambler::generated_by(&[Move::R, Move::U])?;
+/// let scramble = scrambler.scramble(15, &mut StdRng::seed_from_u64(1));
+/// assert_eq!(scramble.len(), 15);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct RandomMoveScrambler {
+    moves: Vec<Move>,
+}
+
+impl RandomMoveScrambler {
+    /// Creates a scrambler that turns the outer faces, see [`Metric::Half`].
+    pub fn new() -> Self {
+        Self {
+            moves: Metric::Half.moves().to_vec(),
+        }
+    }
+
+    /// Creates a scrambler that picks from exactly `moves`.
+    ///
+    /// Fails if any of the moves isn't a face or slice move, or if they don't turn around at least two
+    /// different axes.
+    pub fn with_moves(moves: &[Move]) -> Result<Self, MoveSetError> {
+        let mut axes = Vec::new();
+        for &m in moves {
+            let (axis, _) = m.layer().ok_or(MoveSetError::NotALayerTurn(m))?;
+            if !axes.contains(&axis) {
+                axes.push(axis);
+            }
+        }
+        if axes.len() < 2 {
+            return Err(MoveSetError::SingleAxis);
+        }
+
+        Ok(Self {
+            moves: moves.to_vec(),
+        })
+    }
+
+    /// Creates a scrambler that picks from all turns of the layers of `generators`, e.g. R, R', R2, U,
+    /// U' and U2 for the ⟨R, U⟩ group generated by R and U. See [`RandomMoveScrambler::with_moves`].
+    pub fn generated_by(generators: &[Move]) -> Result<Self, MoveSetError> {
+        let mut moves = Vec::new();
+        for &generator in generators {
+            generator
+                .layer()
+                .ok_or(MoveSetError::NotALayerTurn(generator))?;
+            for m in Metric::Slice.moves() {
+                if m.layer() == generator.layer() && !moves.contains(m) {
+                    moves.push(*m);
+                }
+            }
+        }
+        Self::with_moves(&moves)
+    }
+
+    /// The moves scrambles are made of.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// A scramble of `length` random moves.
+    pub fn scramble(&self, length: usize, rng: &mut impl Rng) -> Vec<Move> {
+        let mut scramble: Vec<Move> = Vec::with_capacity(length);
+        while scramble.len() < length {
+            let m = self.moves[rng.random_range(0..self.moves.len())];
+            let layer = m.layer();
+            let axis = layer.unwrap().0;
+            // turns of the same axis commute, so R L M2 R is just L M2 R2
+            let redundant = scramble
+                .iter()
+                .rev()
+                .take_while(|before| before.layer().unwrap().0 == axis)
+                .any(|before| before.layer() == layer);
+            if !redundant {
+                scramble.push(m);
+            }
+        }
+        scramble
+    }
+}
+
+impl Default for RandomMoveScrambler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::turn::Turnable;
+
+    fn scrambler() -> &'static RandomStateScrambler {
+        static SCRAMBLER: OnceLock<RandomStateScrambler> = OnceLock::new();
+        SCRAMBLER.get_or_init(RandomStateScrambler::new)
+    }
+
+    #[test]
+    fn random_states() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut twisted = 0;
+        for _ in 0..1000 {
+            let cube = random_state(&mut rng);
+            assert_eq!(cube.validate(), Ok(()));
+
+            let cubies = CubieCube::try_from(cube).unwrap();
+            twisted += (cubies.co[0] != 0) as usize;
+        }
+        // a corner is twisted in two thirds of all positions
+        assert!((600..730).contains(&twisted), "{twisted}");
+    }
+
+    #[test]
+    fn scrambles() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let scramble = scrambler().scramble(&mut rng);
+        assert!(scramble.len() <= 21);
+
+        // the scramble leads to the position it was generated from
+        let mut cube = Cube::default();
+        cube.perform_all(&scramble);
+        assert_eq!(cube, random_state(&mut StdRng::seed_from_u64(7)));
+
+        assert_eq!(
+            scrambler().scramble(&mut StdRng::seed_from_u64(7)),
+            scramble
+        );
+    }
+
+    #[test]
+    fn min_length() {
+        let scrambler = RandomStateScrambler::new().min_length(6);
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..5 {
+            assert!(scrambler.scramble(&mut rng).len() >= 6);
+        }
+
+        // positions near the solved cube are skipped by their optimal distance
+        let mut cube = Cube::default();
+        cube.perform_all(&[Move::R, Move::U, Move::FPrime, Move::L2]);
+        for min_length in 0..=4 {
+            let scrambler = RandomStateScrambler::new().min_length(min_length);
+            let scramble = scrambler.scramble_to(cube).unwrap();
+            let mut scrambled = Cube::default();
+            scrambled.perform_all(&scramble);
+            assert_eq!(scrambled, cube);
+        }
+        assert_eq!(
+            RandomStateScrambler::new().min_length(5).scramble_to(cube),
+            None
+        );
+        assert_eq!(
+            RandomStateScrambler::new()
+                .min_length(2)
+                .scramble_to(Cube::default()),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic = "more than the maximum of 20"]
+    fn min_length_too_long() {
+        let _ = RandomStateScrambler::new().min_length(30);
+    }
+
+    #[test]
+    #[should_panic = "more than the maximum of 15"]
+    fn min_length_over_max_length() {
+        let _ = RandomStateScrambler::new().max_length(15).min_length(16);
+    }
+
+    #[test]
+    #[should_panic = "less than the minimum of 6"]
+    fn max_length_under_min_length() {
+        let _ = RandomStateScrambler::new().min_length(6).max_length(5);
+    }
+
+    /// Checks that no move of `scramble` is redundant, see [`RandomMoveScrambler`].
+    fn assert_canonical(scramble: &[Move]) {
+        for (i, m) in scramble.iter().enumerate() {
+            let layer = m.layer().unwrap();
+            for before in scramble[..i].iter().rev() {
+                let before = before.layer().unwrap();
+                if before.0 != layer.0 {
+                    break;
+                }
+                assert_ne!(before, layer, "{scramble:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn random_moves() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let scrambler = RandomMoveScrambler::new();
+        for _ in 0..100 {
+            let scramble = scrambler.scramble(25, &mut rng);
+            assert_eq!(scramble.len(), 25);
+            assert_canonical(&scramble);
+        }
+
+        let scramble = scrambler.scramble(25, &mut StdRng::seed_from_u64(1));
+        assert_eq!(
+            scrambler.scramble(25, &mut StdRng::seed_from_u64(1)),
+            scramble
+        );
+        assert!(scrambler.scramble(0, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn move_sets() {
+        use crate::turn::Move::*;
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let scrambler = RandomMoveScrambler::generated_by(&[R, U]).unwrap();
+        assert_eq!(scrambler.moves(), [R, RPrime, R2, U, UPrime, U2]);
+        let scramble = scrambler.scramble(30, &mut rng);
+        assert!(scramble.iter().all(|m| scrambler.moves().contains(m)));
+        // with two layers, the moves have to alternate
+        assert_canonical(&scramble);
+
+        let scrambler = RandomMoveScrambler::with_moves(&[R, L, M2, U]).unwrap();
+        assert_canonical(&scrambler.scramble(100, &mut rng));
+        // three layers of one axis can be turned in a row, but none of them twice
+        let scrambler = RandomMoveScrambler::generated_by(&[R, L, M, U]).unwrap();
+        assert_eq!(scrambler.moves().len(), 12);
+        let scramble = scrambler.scramble(200, &mut rng);
+        assert_canonical(&scramble);
+        assert!(scramble.windows(3).any(|w| w
+            .iter()
+            .all(|m| m.layer().unwrap().0 == R.layer().unwrap().0)));
+
+        assert_eq!(
+            RandomMoveScrambler::generated_by(&[R, L2, M]).unwrap_err(),
+            MoveSetError::SingleAxis
+        );
+        assert_eq!(
+            RandomMoveScrambler::with_moves(&[R, Rw]).unwrap_err(),
+            MoveSetError::NotALayerTurn(Rw)
+        );
+    }
+}
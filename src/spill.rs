@@ -0,0 +1,298 @@
+//! Sorted files of search layers, for searches that don't fit in memory.
+//!
+//! A layer is stored as fixed size records sorted by cube, so two layers can be compared by reading both
+//! in order (a merge join) and a single class can be found with a binary search.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::cube::Cube;
+
+/// A class of the search together with the moves that are worth trying from it, as a bit set.
+pub(crate) type Record = (Cube, u32);
+
+const RECORD_BYTES: u64 = Cube::BYTES as u64 + 4;
+
+fn read_record(reader: &mut impl Read) -> io::Result<Record> {
+    let mut cube = [0; Cube::BYTES];
+    let mut moves = [0; 4];
+    reader.read_exact(&mut cube)?;
+    reader.read_exact(&mut moves)?;
+
+    let cube = Cube::from_bytes(&cube)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt layer file"))?;
+    Ok((cube, u32::from_le_bytes(moves)))
+}
+
+/// Sorts `records` by cube and combines the moves of records with the same cube.
+pub(crate) fn sort(mut records: Vec<Record>) -> Vec<Record> {
+    records.sort_unstable_by_key(|&(cube, _)| cube);
+    records.dedup_by(|(cube, moves), (kept, kept_moves)| {
+        let duplicate = cube == kept;
+        if duplicate {
+            *kept_moves |= *moves;
+        }
+        duplicate
+    });
+    records
+}
+
+/// Writes records to a new [`Layer`] file.
+pub(crate) struct LayerWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    len: u64,
+}
+
+impl LayerWriter {
+    pub(crate) fn create(path: PathBuf) -> io::Result<Self> {
+        let file = File::options().write(true).create_new(true).open(&path)?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+            len: 0,
+        })
+    }
+
+    /// Appends a record, which has to come after all records written before.
+    pub(crate) fn push(&mut self, (cube, moves): Record) -> io::Result<()> {
+        self.writer.write_all(&cube.to_bytes())?;
+        self.writer.write_all(&moves.to_le_bytes())?;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<Layer> {
+        self.writer.flush()?;
+        Ok(Layer {
+            file: File::open(&self.path)?,
+            path: self.path,
+            len: self.len,
+        })
+    }
+}
+
+/// A file of records sorted by cube, with every cube at most once.
+#[derive(Debug)]
+pub(crate) struct Layer {
+    path: PathBuf,
+    /// Kept open for lookups, reading the whole layer opens the file again.
+    file: File,
+    len: u64,
+}
+
+impl Layer {
+    /// Writes sorted records without duplicates to a new file at `path`.
+    pub(crate) fn write(path: PathBuf, records: &[Record]) -> io::Result<Self> {
+        let mut writer = LayerWriter::create(path)?;
+        for &record in records {
+            writer.push(record)?;
+        }
+        writer.finish()
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads all records in order.
+    pub(crate) fn iter(&self) -> io::Result<Records> {
+        Ok(Records {
+            reader: BufReader::new(File::open(&self.path)?),
+            remaining: self.len,
+        })
+    }
+
+    /// Finds the moves stored with `cube` with a binary search.
+    pub(crate) fn get(&self, cube: &Cube) -> io::Result<Option<u32>> {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let middle = (low + high) / 2;
+            let mut file = &self.file;
+            file.seek(SeekFrom::Start(middle * RECORD_BYTES))?;
+            let (found, moves) = read_record(&mut file)?;
+            match found.cmp(cube) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Ok(Some(moves)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The records of a [`Layer`] in order.
+pub(crate) struct Records {
+    reader: BufReader<File>,
+    remaining: u64,
+}
+
+impl Iterator for Records {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(read_record(&mut self.reader))
+    }
+}
+
+/// Looks up cubes in a [`Layer`] by reading it once, which works as long as the cubes are looked up in
+/// increasing order.
+pub(crate) struct Join {
+    records: Records,
+    current: Option<Record>,
+}
+
+impl Join {
+    pub(crate) fn new(layer: &Layer) -> io::Result<Self> {
+        let mut records = layer.iter()?;
+        let current = records.next().transpose()?;
+        Ok(Self { records, current })
+    }
+
+    /// Finds the moves stored with `cube`, which can't be smaller than the cubes looked up before.
+    pub(crate) fn find(&mut self, cube: &Cube) -> io::Result<Option<u32>> {
+        while let Some((current, moves)) = self.current {
+            match current.cmp(cube) {
+                Ordering::Less => self.current = self.records.next().transpose()?,
+                Ordering::Equal => return Ok(Some(moves)),
+                Ordering::Greater => break,
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The records of several layers in order, combining the moves of records with the same cube.
+pub(crate) struct Merge {
+    sources: Vec<Records>,
+    /// The smallest record of every source that hasn't been returned yet.
+    heads: BinaryHeap<Reverse<(Cube, u32, usize)>>,
+}
+
+impl Merge {
+    pub(crate) fn new(layers: &[Layer]) -> io::Result<Self> {
+        let mut merge = Self {
+            sources: layers.iter().map(Layer::iter).collect::<io::Result<_>>()?,
+            heads: BinaryHeap::new(),
+        };
+        for source in 0..merge.sources.len() {
+            merge.advance(source)?;
+        }
+        Ok(merge)
+    }
+
+    fn advance(&mut self, source: usize) -> io::Result<()> {
+        if let Some((cube, moves)) = self.sources[source].next().transpose()? {
+            self.heads.push(Reverse((cube, moves, source)));
+        }
+        Ok(())
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let Some(Reverse((cube, mut moves, source))) = self.heads.pop() else {
+            return Ok(None);
+        };
+        self.advance(source)?;
+        while let Some(&Reverse((next, next_moves, source))) = self.heads.peek() {
+            if next != cube {
+                break;
+            }
+            moves |= next_moves;
+            self.heads.pop();
+            self.advance(source)?;
+        }
+        Ok(Some((cube, moves)))
+    }
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turn::{Metric, Turnable};
+
+    fn cubes() -> Vec<Cube> {
+        Metric::Half
+            .moves()
+            .iter()
+            .map(|&m| {
+                let mut cube = Cube::default();
+                cube.perform(m);
+                cube
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let cubes = cubes();
+
+        let records = sort(
+            cubes
+                .iter()
+                .enumerate()
+                .map(|(i, &cube)| (cube, 1 << (i % 3)))
+                .chain([(cubes[0], 0b1000)])
+                .collect(),
+        );
+        assert_eq!(records.len(), cubes.len());
+        let layer = Layer::write(dir.path().join("layer"), &records).unwrap();
+
+        let read = layer
+            .iter()
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, records);
+        assert_eq!(layer.get(&cubes[0]).unwrap(), Some(0b1001));
+        assert_eq!(layer.get(&cubes[4]).unwrap(), Some(0b10));
+        assert_eq!(layer.get(&Cube::default()).unwrap(), None);
+
+        let mut join = Join::new(&layer).unwrap();
+        for &(cube, moves) in &records {
+            assert_eq!(join.find(&cube).unwrap(), Some(moves));
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let cubes = cubes();
+
+        let first = sort(cubes[..10].iter().map(|&cube| (cube, 1)).collect());
+        let second = sort(cubes[5..].iter().map(|&cube| (cube, 2)).collect());
+        let layers = [
+            Layer::write(dir.path().join("first"), &first).unwrap(),
+            Layer::write(dir.path().join("second"), &second).unwrap(),
+        ];
+
+        let merged = Merge::new(&layers)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        let expected = sort(first.into_iter().chain(second).collect());
+        assert_eq!(merged, expected);
+        assert_eq!(merged.iter().filter(|&&(_, moves)| moves == 3).count(), 5);
+    }
+}
@@ -0,0 +1,292 @@
+//! The 48 symmetries of the cube: the 24 rotations, each with and without mirroring it left to right.
+//!
+//! Turning a cube, then looking at it from another side (or in a mirror) and renaming the colors so the
+//! centers are back to normal gives a position that needs exactly as many moves to solve. All positions
+//! related like this form a class with a single [`representative`], so a search only has to visit one
+//! position per class instead of up to 48.
+
+use std::sync::OnceLock;
+
+use crate::{
+    cube::{Color, Cube, Face},
+    turn::{Metric, Move, Turnable},
+};
+
+/// The number of symmetries of the cube.
+pub const SYMMETRY_COUNT: usize = 48;
+
+const STICKERS: usize = 54;
+
+/// The colors of the faces in the default orientation, in the order of [`Cube`]'s faces. A position is
+/// unpacked into the index of each sticker's color in this list.
+const FACE_COLORS: [Color; 6] = [
+    Color::White,
+    Color::Yellow,
+    Color::Orange,
+    Color::Red,
+    Color::Green,
+    Color::Blue,
+];
+
+/// A symmetry as the position (`face * 9 + index`) every sticker is moved to.
+type Permutation = [u8; STICKERS];
+
+/// The symmetries, each as the position every sticker comes from. That is the order they're needed in
+/// when building the moved cube sticker by sticker.
+fn inverse_symmetries() -> &'static [Permutation; SYMMETRY_COUNT] {
+    static INVERSES: OnceLock<[Permutation; SYMMETRY_COUNT]> = OnceLock::new();
+    INVERSES.get_or_init(|| {
+        symmetries().map(|symmetry| {
+            let mut inverse = [0; STICKERS];
+            for (from, &to) in symmetry.iter().enumerate() {
+                inverse[to as usize] = from as u8;
+            }
+            inverse
+        })
+    })
+}
+
+fn symmetries() -> &'static [Permutation; SYMMETRY_COUNT] {
+    static SYMMETRIES: OnceLock<[Permutation; SYMMETRY_COUNT]> = OnceLock::new();
+    SYMMETRIES.get_or_init(|| {
+        let identity: Permutation = std::array::from_fn(|p| p as u8);
+        let generators = [permutation(Move::X), permutation(Move::Y), mirror()];
+
+        // everything that can be reached by combining the generators
+        let mut symmetries = vec![identity];
+        let mut i = 0;
+        while i < symmetries.len() {
+            for generator in &generators {
+                let combined = symmetries[i].map(|p| generator[p as usize]);
+                if !symmetries.contains(&combined) {
+                    symmetries.push(combined);
+                }
+            }
+            i += 1;
+        }
+
+        symmetries.try_into().expect("the cube has 48 symmetries")
+    })
+}
+
+/// Where every sticker goes when the cube is turned by `m`.
+fn permutation(m: Move) -> Permutation {
+    std::array::from_fn(|from| {
+        let mut cube = Cube::default();
+        for face in 0..6 {
+            *cube.face_mut(face) = Face::WHITE;
+        }
+        cube.face_mut(from / 9).set(from % 9, Color::Yellow);
+        cube.perform(m);
+
+        (0..STICKERS)
+            .find(|&to| cube.face(to / 9).get(to % 9) == Color::Yellow)
+            .unwrap() as u8
+    })
+}
+
+/// Where every sticker goes when the cube is mirrored at the plane between the left and right face.
+fn mirror() -> Permutation {
+    std::array::from_fn(|from| {
+        let face = match from / 9 {
+            Cube::LEFT => Cube::RIGHT,
+            Cube::RIGHT => Cube::LEFT,
+            face => face,
+        };
+        let (row, column) = (from % 9 / 3, from % 3);
+        (face * 9 + row * 3 + 2 - column) as u8
+    })
+}
+
+fn unpack(cube: &Cube) -> [u8; STICKERS] {
+    let mut stickers = [0; STICKERS];
+    for (face, stickers) in stickers.chunks_mut(9).enumerate() {
+        for (sticker, color) in stickers.iter_mut().zip(cube.face(face).colors()) {
+            *sticker = match color {
+                Color::White => 0,
+                Color::Yellow => 1,
+                Color::Orange => 2,
+                Color::Red => 3,
+                Color::Green => 4,
+                Color::Blue => 5,
+            };
+        }
+    }
+    stickers
+}
+
+fn pack(stickers: &[u8; STICKERS]) -> Cube {
+    let mut cube = Cube::default();
+    for (face, stickers) in stickers.chunks(9).enumerate() {
+        *cube.face_mut(face) =
+            Face::from_colors(std::array::from_fn(|i| FACE_COLORS[stickers[i] as usize]));
+    }
+    cube
+}
+
+/// The new color of every color when the stickers are moved to where `inverse` takes them from, such
+/// that every center gets the color of its face in the default orientation.
+fn renaming(stickers: &[u8; STICKERS], inverse: &Permutation) -> [u8; 6] {
+    let mut rename = [0; 6];
+    for face in 0..6 {
+        rename[stickers[inverse[face * 9 + 4] as usize] as usize] = face as u8;
+    }
+    rename
+}
+
+/// The position `cube` becomes when looked at with the given symmetry, where `symmetry` is in
+/// `0..SYMMETRY_COUNT` and 0 is the identity.
+pub fn conjugate(cube: &Cube, symmetry: usize) -> Cube {
+    let stickers = unpack(cube);
+    let inverse = &inverse_symmetries()[symmetry];
+    let rename = renaming(&stickers, inverse);
+    pack(&inverse.map(|from| rename[stickers[from as usize] as usize]))
+}
+
+/// The number of face and slice moves, the only moves [`conjugate_move`] works for.
+pub(crate) const LAYER_MOVES: usize = 27;
+
+/// Returns the move that does to a position looked at with `symmetry` what `m` does to the original
+/// position. For example, mirroring the cube turns `R` into `L'`.
+///
+/// # Panic
+/// This function panics if `m` is a wide move or a rotation.
+pub fn conjugate_move(m: Move, symmetry: usize) -> Move {
+    static MOVES: OnceLock<[[Move; LAYER_MOVES]; SYMMETRY_COUNT]> = OnceLock::new();
+    let moves = MOVES.get_or_init(|| {
+        let mut layer_moves: [Move; LAYER_MOVES] = Metric::Slice.moves().try_into().unwrap();
+        layer_moves.sort_by_key(|&m| m as usize);
+        let permutations = layer_moves.map(permutation);
+
+        std::array::from_fn(|s| {
+            let (symmetry, inverse) = (&symmetries()[s], &inverse_symmetries()[s]);
+            permutations.map(|moved| {
+                let conjugated = inverse.map(|p| symmetry[moved[p as usize] as usize]);
+                let i = permutations.iter().position(|&p| p == conjugated).unwrap();
+                layer_moves[i]
+            })
+        })
+    });
+
+    assert!(
+        (m as usize) < LAYER_MOVES,
+        "{m:?} is not a face or slice move"
+    );
+    moves[symmetry][m as usize]
+}
+
+/// The representative of the class of all positions that are symmetric to `cube`. Two cubes have the
+/// same representative exactly when one can be turned into the other by a symmetry.
+///
+/// The centers of the representative are always in the default orientation, so cubes that only differ
+/// in their orientation share a representative as well.
+pub fn representative(cube: &Cube) -> Cube {
+    representative_with_symmetry(cube).0
+}
+
+/// Like [`representative`], but also returns a symmetry that turns `cube` into its representative. If
+/// the position is symmetric itself there are several, and the first one is returned.
+pub fn representative_with_symmetry(cube: &Cube) -> (Cube, usize) {
+    let stickers = unpack(cube);
+    let mut best = [u8::MAX; STICKERS];
+    let mut best_symmetry = 0;
+    for (symmetry, inverse) in inverse_symmetries().iter().enumerate() {
+        let rename = renaming(&stickers, inverse);
+
+        // the representative is the smallest of all symmetric positions, and most of them can be
+        // ruled out after a few stickers
+        for p in 0..STICKERS {
+            let color = rename[stickers[inverse[p] as usize] as usize];
+            if color > best[p] {
+                break;
+            }
+            if color < best[p] {
+                best[p] = color;
+                for q in p + 1..STICKERS {
+                    best[q] = rename[stickers[inverse[q] as usize] as usize];
+                }
+                best_symmetry = symmetry;
+                break;
+            }
+        }
+    }
+    (pack(&best), best_symmetry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turn::Move::*;
+
+    #[test]
+    fn symmetries_are_distinct() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U2, F, LPrime, D, B2]);
+
+        let mut conjugates = (0..SYMMETRY_COUNT)
+            .map(|symmetry| conjugate(&cube, symmetry))
+            .collect::<Vec<_>>();
+        assert_eq!(conjugates[0], cube);
+        for conjugate in &conjugates {
+            assert_eq!(conjugate.validate(), Ok(()));
+            assert_eq!(representative(conjugate), representative(&cube));
+        }
+
+        conjugates.sort_by_key(|c| c.to_string());
+        conjugates.dedup();
+        assert_eq!(conjugates.len(), SYMMETRY_COUNT);
+    }
+
+    #[test]
+    fn mirrored_moves() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, RPrime]);
+        let mut mirrored = Cube::default();
+        mirrored.perform_all(&[LPrime, UPrime, L]);
+
+        assert_eq!(representative(&cube), representative(&mirrored));
+    }
+
+    #[test]
+    fn conjugated_moves() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, FPrime, M, D2]);
+        let cube = representative(&cube);
+
+        for &m in Metric::Slice.moves() {
+            let mut moved = cube;
+            moved.perform(m);
+            let (next, symmetry) = representative_with_symmetry(&moved);
+            assert_eq!(conjugate(&moved, symmetry), next);
+
+            // undoing the move as seen from the representative leads back to the class it came from
+            let mut previous = next;
+            previous.perform(conjugate_move(m, symmetry).inverse());
+            assert_eq!(representative(&previous), cube, "{m:?}");
+        }
+
+        assert_eq!(conjugate_move(R, 0), R);
+        assert_eq!(conjugate_move(M2, 0), M2);
+    }
+
+    #[test]
+    fn orientations() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, FPrime]);
+
+        for orientation in cube.orientations() {
+            assert_eq!(representative(&orientation), representative(&cube));
+        }
+        assert_eq!(representative(&Cube::default()), Cube::default());
+    }
+
+    #[test]
+    fn different_classes() {
+        let mut sexy = Cube::default();
+        sexy.perform_all(&[R, U, RPrime, UPrime]);
+        let mut sune = Cube::default();
+        sune.perform_all(&[R, U, RPrime, U, R, U2, RPrime]);
+
+        assert_ne!(representative(&sexy), representative(&sune));
+    }
+}
@@ -0,0 +1,412 @@
+//! Precomputed distances to the solved cube, to skip the solved cube's half of every search.
+//!
+//! The solved cube's front of a [`Solver`](crate::Solver) is the same for every scramble. A
+//! [`SolvedTable`] stores it up to some depth, can be saved to a file once and mapped into memory by
+//! every later run. Solvers created with [`Solver::with_table`](crate::Solver::with_table) then only
+//! search from the scramble until they reach a class in the table.
+//!
+//! # File format
+//! All numbers are little endian.
+//!
+//! | bytes | content |
+//! |---|---|
+//! | 8 | the magic bytes `CUBERSTB` |
+//! | 4 | the format version, [`SolvedTable::VERSION`] |
+//! | 1 | the metric: 0 for quarter, 1 for half and 2 for slice turns |
+//! | 1 | the depth of the table |
+//! | 2 | zero |
+//! | 4 | the CRC-32 of the whole file, without these 4 bytes |
+//! | 8 per layer | the number of classes at each distance, from 0 to the depth |
+//! | 48 per class | the representative of every class, sorted within its layer |
+//!
+//! The faces of a representative are stored as big endian numbers, so comparing their bytes sorts them
+//! like [`Cube`]s.
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Write},
+    ops::{Deref, Range},
+    path::Path,
+};
+
+use memmap2::Mmap;
+use rayon::prelude::*;
+
+use crate::{
+    cube::{Cube, Face},
+    symmetry::representative,
+    turn::{Metric, Turnable},
+};
+
+const MAGIC: &[u8; 8] = b"CUBERSTB";
+
+/// The bytes before the layer sizes.
+const HEADER_BYTES: usize = 20;
+
+/// Where the checksum is stored in the header.
+const CHECKSUM: Range<usize> = 16..20;
+
+/// The CRC-32 of a table's bytes, which covers the header too, except for the checksum itself.
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&bytes[..CHECKSUM.start]);
+    hasher.update(&bytes[CHECKSUM.end..]);
+    hasher.finalize()
+}
+
+type Key = [u8; Cube::BYTES];
+
+fn key(cube: &Cube) -> Key {
+    let mut key = [0; Cube::BYTES];
+    for (chunk, face) in key.chunks_mut(8).zip(0..6) {
+        chunk.copy_from_slice(&cube.face(face).to_bits().to_be_bytes());
+    }
+    key
+}
+
+fn metric_byte(metric: Metric) -> u8 {
+    match metric {
+        Metric::Quarter => 0,
+        Metric::Half => 1,
+        Metric::Slice => 2,
+    }
+}
+
+/// The reason a file can't be loaded as a [`SolvedTable`].
+#[derive(Debug)]
+pub enum TableError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// The file doesn't start with the magic bytes of a table.
+    NotATable,
+    /// The file was written in a format version this crate can't read.
+    Version(u32),
+    /// The file is truncated or its checksum doesn't match, it has to be generated again.
+    Corrupt,
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::Io(error) => write!(f, "{error}"),
+            TableError::NotATable => write!(f, "the file is not a table of solved positions"),
+            TableError::Version(version) => write!(
+                f,
+                "the table has version {version}, but only version {} is supported",
+                SolvedTable::VERSION
+            ),
+            TableError::Corrupt => write!(f, "the table is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for TableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TableError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TableError {
+    fn from(error: io::Error) -> Self {
+        TableError::Io(error)
+    }
+}
+
+/// The bytes of a table, as generated or mapped from a file.
+#[derive(Debug)]
+enum Storage {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Owned(bytes) => bytes,
+            Storage::Mapped(map) => map,
+        }
+    }
+}
+
+/// Every class of positions up to some distance from the solved cube, by distance.
+///
+/// ```
+/// use cubers::{table::SolvedTable, Cube, Metric, Move, Turnable};
+///
+/// let table = SolvedTable::generate(Metric::Half, 2);
+/// let mut cube = Cube::default();
+/// cube.perform_all(&[Move::R, Move::U2]);
+/// assert_eq!(table.distance(&cube), Some(2));
+///
+/// cube.perform(Move::F);
+/// assert_eq!(table.distance(&cube), None);
+/// ```
+#[derive(Debug)]
+pub struct SolvedTable {
+    metric: Metric,
+    /// The whole file, including the header.
+    bytes: Storage,
+    /// The records of each layer as a range of bytes.
+    layers: Vec<Range<usize>>,
+}
+
+impl SolvedTable {
+    /// The version of the file format that [`SolvedTable::save`] writes and [`SolvedTable::load`]
+    /// reads. It changes whenever files of older versions can't be read anymore.
+    pub const VERSION: u32 = 2;
+
+    /// Runs a breadth first search from the solved cube to find every class up to `depth` moves away
+    /// in `metric`, on as many threads as there are CPUs.
+    ///
+    /// Each depth has roughly 13 times as many classes as the one before in the half turn metric,
+    /// and a class takes 48 bytes. Depth 7 needs a few hundred megabytes.
+    pub fn generate(metric: Metric, depth: u8) -> Self {
+        let mut layers: Vec<Vec<Key>> = vec![vec![key(&Cube::default())]];
+        while layers.len() <= depth as usize {
+            let last = layers.last().unwrap();
+            // neighbors of a layer can only be in the layer before, the layer itself or the next one
+            let previous = &layers[layers.len().saturating_sub(2)..];
+            let mut next = last
+                .par_iter()
+                .flat_map_iter(|class| {
+                    let state = Self::decode(class);
+                    metric.moves().iter().map(move |&m| {
+                        let mut next = state;
+                        next.perform(m);
+                        key(&representative(&next))
+                    })
+                })
+                .filter(|key| {
+                    previous
+                        .iter()
+                        .all(|layer| layer.binary_search(key).is_err())
+                })
+                .collect::<Vec<_>>();
+            next.par_sort_unstable();
+            next.dedup();
+            layers.push(next);
+        }
+
+        let mut bytes = Vec::with_capacity(
+            HEADER_BYTES
+                + 8 * layers.len()
+                + Cube::BYTES * layers.iter().map(Vec::len).sum::<usize>(),
+        );
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&Self::VERSION.to_le_bytes());
+        bytes.extend_from_slice(&[metric_byte(metric), depth, 0, 0]);
+        bytes.extend_from_slice(&[0; 4]);
+        for layer in &layers {
+            bytes.extend_from_slice(&(layer.len() as u64).to_le_bytes());
+        }
+        for key in layers.iter().flatten() {
+            bytes.extend_from_slice(key);
+        }
+        let checksum = checksum(&bytes);
+        bytes[CHECKSUM].copy_from_slice(&checksum.to_le_bytes());
+
+        Self::parse(Storage::Owned(bytes)).expect("generated tables are valid")
+    }
+
+    /// Maps a table written by [`SolvedTable::save`] into memory, after checking its version and
+    /// checksum. The file has to stay unchanged as long as the table is used.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TableError> {
+        let file = File::open(path)?;
+        // SAFETY: the table is only read, and the file mustn't change while it is used (see above)
+        let map = unsafe { Mmap::map(&file)? };
+        let table = Self::parse(Storage::Mapped(map))?;
+        if checksum(&table.bytes) != table.checksum() {
+            return Err(TableError::Corrupt);
+        }
+        Ok(table)
+    }
+
+    /// Writes the table to a new file at `path`, replacing any file that is already there.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(&self.bytes)?;
+        file.sync_all()
+    }
+
+    /// Reads the header of a table, without checking the checksum.
+    fn parse(bytes: Storage) -> Result<Self, TableError> {
+        if bytes.len() < HEADER_BYTES || &bytes[..8] != MAGIC {
+            return Err(TableError::NotATable);
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        if version != Self::VERSION {
+            return Err(TableError::Version(version));
+        }
+        let metric = match bytes[12] {
+            0 => Metric::Quarter,
+            1 => Metric::Half,
+            2 => Metric::Slice,
+            _ => return Err(TableError::Corrupt),
+        };
+
+        let depth = bytes[13] as usize;
+        let mut start = HEADER_BYTES + 8 * (depth + 1);
+        let sizes = bytes.get(HEADER_BYTES..start).ok_or(TableError::Corrupt)?;
+        let mut layers = Vec::with_capacity(depth + 1);
+        for size in sizes.chunks(8) {
+            let size = u64::from_le_bytes(size.try_into().unwrap()) as usize;
+            let end = size
+                .checked_mul(Cube::BYTES)
+                .and_then(|len| start.checked_add(len))
+                .ok_or(TableError::Corrupt)?;
+            layers.push(start..end);
+            start = end;
+        }
+        if start != bytes.len() {
+            return Err(TableError::Corrupt);
+        }
+
+        Ok(Self {
+            metric,
+            bytes,
+            layers,
+        })
+    }
+
+    fn checksum(&self) -> u32 {
+        u32::from_le_bytes(self.bytes[CHECKSUM].try_into().unwrap())
+    }
+
+    fn decode(key: &Key) -> Cube {
+        let mut cube = Cube::default();
+        for (chunk, face) in key.chunks(8).zip(0..6) {
+            let bits = u64::from_be_bytes(chunk.try_into().unwrap());
+            *cube.face_mut(face) = Face::from_bits(bits).expect("keys are valid cubes");
+        }
+        cube
+    }
+
+    /// The metric the distances are counted in.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// The distance of the farthest classes in the table.
+    pub fn depth(&self) -> u8 {
+        (self.layers.len() - 1) as u8
+    }
+
+    /// The number of classes in the table.
+    pub fn len(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| layer.len() / Cube::BYTES)
+            .sum()
+    }
+
+    /// Whether the table is empty, which it never is since it contains the solved cube.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many moves it takes to solve `cube`, or `None` if that's more than [`SolvedTable::depth`].
+    pub fn distance(&self, cube: &Cube) -> Option<u8> {
+        self.class_distance(&representative(cube))
+    }
+
+    /// Like [`SolvedTable::distance`], for the representative of a class.
+    pub(crate) fn class_distance(&self, class: &Cube) -> Option<u8> {
+        let key = key(class);
+        self.layers
+            .iter()
+            .position(|layer| self.find(layer.clone(), &key))
+            .map(|depth| depth as u8)
+    }
+
+    fn find(&self, layer: Range<usize>, key: &Key) -> bool {
+        let records = &self.bytes[layer];
+        let (mut low, mut high) = (0, records.len() / Cube::BYTES);
+        while low < high {
+            let middle = (low + high) / 2;
+            let record = &records[middle * Cube::BYTES..(middle + 1) * Cube::BYTES];
+            match record.cmp(key) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return true,
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turn::Move::*;
+
+    #[test]
+    fn distances() {
+        let table = SolvedTable::generate(Metric::Half, 3);
+        assert_eq!(table.depth(), 3);
+        assert_eq!(table.metric(), Metric::Half);
+        // the number of classes of positions up to 3 half turns from the solved cube
+        let sizes = table
+            .layers
+            .iter()
+            .map(|layer| layer.len() / Cube::BYTES)
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [1, 2, 9, 75]);
+
+        let mut cube = Cube::default();
+        assert_eq!(table.distance(&cube), Some(0));
+        for (moves, distance) in [(vec![R], 1), (vec![R, L], 2), (vec![U2, F], 2)] {
+            let mut cube = Cube::default();
+            cube.perform_all(&moves);
+            assert_eq!(table.distance(&cube), Some(distance), "{moves:?}");
+        }
+        cube.perform_all(&[R, U, F, D]);
+        assert_eq!(table.distance(&cube), None);
+    }
+
+    #[test]
+    fn files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table");
+        let table = SolvedTable::generate(Metric::Quarter, 4);
+        table.save(&path).unwrap();
+
+        let loaded = SolvedTable::load(&path).unwrap();
+        assert_eq!(loaded.metric(), Metric::Quarter);
+        assert_eq!(loaded.len(), table.len());
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, FPrime]);
+        assert_eq!(loaded.distance(&cube), Some(3));
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(SolvedTable::load(&path), Err(TableError::Corrupt)));
+
+        // a table of the wrong metric would give wrong distances
+        bytes[last] ^= 1;
+        bytes[12] = metric_byte(Metric::Half);
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(SolvedTable::load(&path), Err(TableError::Corrupt)));
+
+        bytes[8] = 3;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            SolvedTable::load(&path),
+            Err(TableError::Version(3))
+        ));
+
+        std::fs::write(&path, b"not a table").unwrap();
+        assert!(matches!(
+            SolvedTable::load(&path),
+            Err(TableError::NotATable)
+        ));
+    }
+}
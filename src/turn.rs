@@ -1,15 +1,158 @@
 use crate::cube::Cube;
 
 #[rustfmt::skip]
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Move {
-    L, LPrime,
-    R, RPrime,
-    U, UPrime,
-    D, DPrime,
-    F, FPrime,
-    B, BPrime
+    L, LPrime, L2,
+    R, RPrime, R2,
+    U, UPrime, U2,
+    D, DPrime, D2,
+    F, FPrime, F2,
+    B, BPrime, B2,
+    // slice moves: M turns like L, E like D and S like F
+    M, MPrime, M2,
+    E, EPrime, E2,
+    S, SPrime, S2,
+    // wide moves turn a face together with the slice next to it
+    Lw, LwPrime, Lw2,
+    Rw, RwPrime, Rw2,
+    Uw, UwPrime, Uw2,
+    Dw, DwPrime, Dw2,
+    Fw, FwPrime, Fw2,
+    Bw, BwPrime, Bw2,
+    // whole cube rotations: X turns like R, Y like U and Z like F
+    X, XPrime, X2,
+    Y, YPrime, Y2,
+    Z, ZPrime, Z2
 }
+
+impl Move {
+    /// Returns the move that undoes this move.
+    pub fn inverse(self) -> Self {
+        use Move::*;
+
+        match self {
+            L => LPrime,
+            LPrime => L,
+            R => RPrime,
+            RPrime => R,
+            U => UPrime,
+            UPrime => U,
+            D => DPrime,
+            DPrime => D,
+            F => FPrime,
+            FPrime => F,
+            B => BPrime,
+            BPrime => B,
+            M => MPrime,
+            MPrime => M,
+            E => EPrime,
+            EPrime => E,
+            S => SPrime,
+            SPrime => S,
+            Lw => LwPrime,
+            LwPrime => Lw,
+            Rw => RwPrime,
+            RwPrime => Rw,
+            Uw => UwPrime,
+            UwPrime => Uw,
+            Dw => DwPrime,
+            DwPrime => Dw,
+            Fw => FwPrime,
+            FwPrime => Fw,
+            Bw => BwPrime,
+            BwPrime => Bw,
+            X => XPrime,
+            XPrime => X,
+            Y => YPrime,
+            YPrime => Y,
+            Z => ZPrime,
+            ZPrime => Z,
+            // half turns are their own inverse
+            L2 | R2 | U2 | D2 | F2 | B2 | M2 | E2 | S2 | Lw2 | Rw2 | Uw2 | Dw2 | Fw2 | Bw2 | X2
+            | Y2 | Z2 => self,
+        }
+    }
+
+    /// The axis a face or slice move turns around and the position of its layer along that axis, e.g.
+    /// `(0, 0)` for L, `(0, 1)` for M and `(0, 2)` for R. Wide moves and rotations turn more than one
+    /// layer, so they have none.
+    pub(crate) fn layer(self) -> Option<(u8, u8)> {
+        Some(match self as u8 / 3 {
+            0 => (0, 0),
+            1 => (0, 2),
+            2 => (1, 0),
+            3 => (1, 2),
+            4 => (2, 0),
+            5 => (2, 2),
+            6 => (0, 1),
+            7 => (1, 1),
+            8 => (2, 1),
+            _ => return None,
+        })
+    }
+}
+
+/// The way the length of a solution is counted, which decides what counts as a single move.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// Quarter turn metric: only quarter turns of the outer faces are single moves.
+    #[default]
+    Quarter,
+    /// Half turn metric: quarter and half turns of the outer faces are single moves.
+    Half,
+    /// Slice turn metric: like the half turn metric, but turns of the middle slices are single moves as well.
+    Slice,
+}
+
+impl Metric {
+    /// All moves that count as a single move in this metric.
+    pub fn moves(self) -> &'static [Move] {
+        use Move::*;
+
+        #[rustfmt::skip]
+        const QUARTER: &[Move] = &[
+            R, RPrime,
+            L, LPrime,
+            U, UPrime,
+            D, DPrime,
+            F, FPrime,
+            B, BPrime
+        ];
+        #[rustfmt::skip]
+        const HALF: &[Move] = &[
+            R, RPrime, R2,
+            L, LPrime, L2,
+            U, UPrime, U2,
+            D, DPrime, D2,
+            F, FPrime, F2,
+            B, BPrime, B2
+        ];
+        #[rustfmt::skip]
+        const SLICE: &[Move] = &[
+            R, RPrime, R2,
+            L, LPrime, L2,
+            U, UPrime, U2,
+            D, DPrime, D2,
+            F, FPrime, F2,
+            B, BPrime, B2,
+            M, MPrime, M2,
+            E, EPrime, E2,
+            S, SPrime, S2
+        ];
+
+        match self {
+            Metric::Quarter => QUARTER,
+            Metric::Half => HALF,
+            Metric::Slice => SLICE,
+        }
+    }
+}
+
+/// Everything that can be turned like a rubiks cube.
+///
+/// Implementors only have to provide quarter turns of the six faces and the three whole cube rotations,
+/// all other moves are composed of those.
 pub trait Turnable {
     fn perform_all(&mut self, turns: &[Move]) {
         for &turn in turns {
@@ -21,16 +164,58 @@ pub trait Turnable {
         match r#move {
             Move::L => self.l(),
             Move::LPrime => self.lprime(),
+            Move::L2 => self.l2(),
             Move::R => self.r(),
             Move::RPrime => self.rprime(),
+            Move::R2 => self.r2(),
             Move::U => self.u(),
             Move::UPrime => self.uprime(),
+            Move::U2 => self.u2(),
             Move::D => self.d(),
             Move::DPrime => self.dprime(),
+            Move::D2 => self.d2(),
             Move::F => self.f(),
             Move::FPrime => self.fprime(),
+            Move::F2 => self.f2(),
             Move::B => self.b(),
             Move::BPrime => self.bprime(),
+            Move::B2 => self.b2(),
+            Move::M => self.m(),
+            Move::MPrime => self.mprime(),
+            Move::M2 => self.m2(),
+            Move::E => self.e(),
+            Move::EPrime => self.eprime(),
+            Move::E2 => self.e2(),
+            Move::S => self.s(),
+            Move::SPrime => self.sprime(),
+            Move::S2 => self.s2(),
+            Move::Lw => self.lw(),
+            Move::LwPrime => self.lwprime(),
+            Move::Lw2 => self.lw2(),
+            Move::Rw => self.rw(),
+            Move::RwPrime => self.rwprime(),
+            Move::Rw2 => self.rw2(),
+            Move::Uw => self.uw(),
+            Move::UwPrime => self.uwprime(),
+            Move::Uw2 => self.uw2(),
+            Move::Dw => self.dw(),
+            Move::DwPrime => self.dwprime(),
+            Move::Dw2 => self.dw2(),
+            Move::Fw => self.fw(),
+            Move::FwPrime => self.fwprime(),
+            Move::Fw2 => self.fw2(),
+            Move::Bw => self.bw(),
+            Move::BwPrime => self.bwprime(),
+            Move::Bw2 => self.bw2(),
+            Move::X => self.x(),
+            Move::XPrime => self.xprime(),
+            Move::X2 => self.x2(),
+            Move::Y => self.y(),
+            Move::YPrime => self.yprime(),
+            Move::Y2 => self.y2(),
+            Move::Z => self.z(),
+            Move::ZPrime => self.zprime(),
+            Move::Z2 => self.z2(),
         }
     }
 
@@ -42,6 +227,11 @@ pub trait Turnable {
         self.r();
     }
 
+    fn r2(&mut self) {
+        self.r();
+        self.r();
+    }
+
     fn l(&mut self);
 
     fn lprime(&mut self) {
@@ -50,6 +240,11 @@ pub trait Turnable {
         self.l();
     }
 
+    fn l2(&mut self) {
+        self.l();
+        self.l();
+    }
+
     fn u(&mut self);
 
     fn uprime(&mut self) {
@@ -58,6 +253,11 @@ pub trait Turnable {
         self.u();
     }
 
+    fn u2(&mut self) {
+        self.u();
+        self.u();
+    }
+
     fn d(&mut self);
 
     fn dprime(&mut self) {
@@ -66,6 +266,11 @@ pub trait Turnable {
         self.d();
     }
 
+    fn d2(&mut self) {
+        self.d();
+        self.d();
+    }
+
     fn f(&mut self);
 
     fn fprime(&mut self) {
@@ -74,6 +279,11 @@ pub trait Turnable {
         self.f();
     }
 
+    fn f2(&mut self) {
+        self.f();
+        self.f();
+    }
+
     fn b(&mut self);
 
     fn bprime(&mut self) {
@@ -81,6 +291,191 @@ pub trait Turnable {
         self.b();
         self.b();
     }
+
+    fn b2(&mut self) {
+        self.b();
+        self.b();
+    }
+
+    fn m(&mut self) {
+        self.r();
+        self.lprime();
+        self.xprime();
+    }
+
+    fn mprime(&mut self) {
+        self.rprime();
+        self.l();
+        self.x();
+    }
+
+    fn m2(&mut self) {
+        self.m();
+        self.m();
+    }
+
+    fn e(&mut self) {
+        self.u();
+        self.dprime();
+        self.yprime();
+    }
+
+    fn eprime(&mut self) {
+        self.uprime();
+        self.d();
+        self.y();
+    }
+
+    fn e2(&mut self) {
+        self.e();
+        self.e();
+    }
+
+    fn s(&mut self) {
+        self.fprime();
+        self.b();
+        self.z();
+    }
+
+    fn sprime(&mut self) {
+        self.f();
+        self.bprime();
+        self.zprime();
+    }
+
+    fn s2(&mut self) {
+        self.s();
+        self.s();
+    }
+
+    fn rw(&mut self) {
+        self.l();
+        self.x();
+    }
+
+    fn rwprime(&mut self) {
+        self.lprime();
+        self.xprime();
+    }
+
+    fn rw2(&mut self) {
+        self.rw();
+        self.rw();
+    }
+
+    fn lw(&mut self) {
+        self.r();
+        self.xprime();
+    }
+
+    fn lwprime(&mut self) {
+        self.rprime();
+        self.x();
+    }
+
+    fn lw2(&mut self) {
+        self.lw();
+        self.lw();
+    }
+
+    fn uw(&mut self) {
+        self.d();
+        self.y();
+    }
+
+    fn uwprime(&mut self) {
+        self.dprime();
+        self.yprime();
+    }
+
+    fn uw2(&mut self) {
+        self.uw();
+        self.uw();
+    }
+
+    fn dw(&mut self) {
+        self.u();
+        self.yprime();
+    }
+
+    fn dwprime(&mut self) {
+        self.uprime();
+        self.y();
+    }
+
+    fn dw2(&mut self) {
+        self.dw();
+        self.dw();
+    }
+
+    fn fw(&mut self) {
+        self.b();
+        self.z();
+    }
+
+    fn fwprime(&mut self) {
+        self.bprime();
+        self.zprime();
+    }
+
+    fn fw2(&mut self) {
+        self.fw();
+        self.fw();
+    }
+
+    fn bw(&mut self) {
+        self.f();
+        self.zprime();
+    }
+
+    fn bwprime(&mut self) {
+        self.fprime();
+        self.z();
+    }
+
+    fn bw2(&mut self) {
+        self.bw();
+        self.bw();
+    }
+
+    fn x(&mut self);
+
+    fn xprime(&mut self) {
+        self.x();
+        self.x();
+        self.x();
+    }
+
+    fn x2(&mut self) {
+        self.x();
+        self.x();
+    }
+
+    fn y(&mut self);
+
+    fn yprime(&mut self) {
+        self.y();
+        self.y();
+        self.y();
+    }
+
+    fn y2(&mut self) {
+        self.y();
+        self.y();
+    }
+
+    fn z(&mut self);
+
+    fn zprime(&mut self) {
+        self.z();
+        self.z();
+        self.z();
+    }
+
+    fn z2(&mut self) {
+        self.z();
+        self.z();
+    }
 }
 
 impl Turnable for Cube {
@@ -177,6 +572,48 @@ impl Turnable for Cube {
         self.top_mut()
             .copy_from_positions(&right, &[(2, 0), (5, 1), (8, 2)]);
     }
+
+    fn x(&mut self) {
+        let top = self.top();
+        let front = self.front();
+        let bottom = self.bottom();
+        let back = self.back();
+
+        self.right_mut().cycle_edges_cw();
+        self.left_mut().cycle_edges_ccw();
+        *self.top_mut() = front;
+        *self.front_mut() = bottom;
+        *self.bottom_mut() = back.rotated_half();
+        *self.back_mut() = top.rotated_half();
+    }
+
+    fn y(&mut self) {
+        let front = self.front();
+        let right = self.right();
+        let back = self.back();
+        let left = self.left();
+
+        self.top_mut().cycle_edges_cw();
+        self.bottom_mut().cycle_edges_ccw();
+        *self.front_mut() = right;
+        *self.right_mut() = back;
+        *self.back_mut() = left;
+        *self.left_mut() = front;
+    }
+
+    fn z(&mut self) {
+        let top = self.top();
+        let right = self.right();
+        let bottom = self.bottom();
+        let left = self.left();
+
+        self.front_mut().cycle_edges_cw();
+        self.back_mut().cycle_edges_ccw();
+        *self.right_mut() = top.rotated_cw();
+        *self.bottom_mut() = right.rotated_cw();
+        *self.left_mut() = bottom.rotated_cw();
+        *self.top_mut() = left.rotated_cw();
+    }
 }
 
 #[cfg(test)]
@@ -424,6 +861,129 @@ mod tests {
         assert_eq!(cube, Cube::default());
     }
 
+    #[test]
+    fn x_order() {
+        let mut cube = Cube::default();
+        cube.r();
+
+        let scrambled = cube;
+        cube.x();
+        cube.x();
+        cube.x();
+        cube.x();
+
+        assert_eq!(cube, scrambled);
+    }
+
+    #[test]
+    fn y_order() {
+        let mut cube = Cube::default();
+        cube.r();
+
+        let scrambled = cube;
+        cube.y();
+        cube.y();
+        cube.y();
+        cube.y();
+
+        assert_eq!(cube, scrambled);
+    }
+
+    #[test]
+    fn z_order() {
+        let mut cube = Cube::default();
+        cube.r();
+
+        let scrambled = cube;
+        cube.z();
+        cube.z();
+        cube.z();
+        cube.z();
+
+        assert_eq!(cube, scrambled);
+    }
+
+    #[test]
+    fn rotations_keep_solved() {
+        use Move::*;
+
+        for rotation in [X, XPrime, X2, Y, YPrime, Y2, Z, ZPrime, Z2] {
+            let mut cube = Cube::default();
+            cube.perform(rotation);
+
+            assert!(cube.is_solved(), "{rotation:?}");
+            assert_ne!(cube, Cube::default(), "{rotation:?}");
+        }
+    }
+
+    #[test]
+    fn rotations_move_faces() {
+        use Move::*;
+
+        // after turning the whole cube like R, the bottom face is in front, so F turns what was D before
+        let mut rotated = Cube::default();
+        rotated.perform_all(&[X, F, XPrime]);
+
+        let mut cube = Cube::default();
+        cube.d();
+
+        assert_eq!(rotated, cube);
+    }
+
+    #[test]
+    fn slice_order() {
+        use Move::*;
+
+        for slice in [M, E, S] {
+            let mut cube = Cube::default();
+            cube.perform_all(&[slice, slice, slice, slice]);
+
+            assert_eq!(cube, Cube::default(), "{slice:?}");
+        }
+    }
+
+    #[test]
+    fn wide_is_face_and_slice() {
+        use Move::*;
+
+        for (wide, moves) in [
+            (Rw, [R, MPrime]),
+            (Lw, [L, M]),
+            (Uw, [U, EPrime]),
+            (Dw, [D, E]),
+            (Fw, [F, S]),
+            (Bw, [B, SPrime]),
+        ] {
+            let mut cube = Cube::default();
+            cube.perform(wide);
+
+            let mut expected = Cube::default();
+            expected.perform_all(&moves);
+
+            assert_eq!(cube, expected, "{wide:?}");
+        }
+    }
+
+    #[test]
+    fn inverse_undoes() {
+        use Move::*;
+
+        let moves = [
+            L, LPrime, L2, R, RPrime, R2, U, UPrime, U2, D, DPrime, D2, F, FPrime, F2, B, BPrime,
+            B2, M, MPrime, M2, E, EPrime, E2, S, SPrime, S2, Lw, LwPrime, Lw2, Rw, RwPrime, Rw2,
+            Uw, UwPrime, Uw2, Dw, DwPrime, Dw2, Fw, FwPrime, Fw2, Bw, BwPrime, Bw2, X, XPrime, X2,
+            Y, YPrime, Y2, Z, ZPrime, Z2,
+        ];
+        for m in moves {
+            let mut cube = Cube::default();
+            cube.perform_all(&[R, U, F]);
+            let scrambled = cube;
+            cube.perform_all(&[m, m.inverse()]);
+
+            assert_eq!(cube, scrambled, "{m:?}");
+        }
+    }
+
     #[test]
     // Note that the superflip uses all types of turns, so this is a pretty comprehensive test case
     fn superflip_order() {
@@ -0,0 +1,540 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    cube::{Cube, ValidationError},
+    cubie::{to_move, CubieCube, FACE_TURNS, MOVE_COUNT},
+    turn::Move,
+};
+
+const TWISTS: usize = 2187;
+const FLIPS: usize = 2048;
+/// The number of ways to choose the positions of the four slice edges.
+const SLICE_COMBINATIONS: usize = 495;
+const SLICE_SORTED: usize = 11880;
+const CORNER_PERMUTATIONS: usize = 40320;
+const UD_EDGE_PERMUTATIONS: usize = 40320;
+const SLICE_PERMUTATIONS: usize = 24;
+
+/// The face turns that keep a cube in the subgroup <U, D, R2, L2, F2, B2>, indexed like in [`FACE_TURNS`].
+const PHASE2_MOVES: [usize; 10] = [0, 1, 2, 4, 7, 9, 10, 11, 13, 16];
+
+/// The longest phase 2 solution that is ever needed.
+const MAX_PHASE2_DEPTH: u8 = 18;
+/// Phase 1 is never longer than this.
+const MAX_PHASE1_DEPTH: u8 = 20;
+
+/// Fills a pruning table with the distance of every state to state 0 (the solved state) by breadth first search.
+fn prune_table(len: usize, moves: usize, neighbor: impl Fn(usize, usize) -> usize) -> Vec<u8> {
+    let mut table = vec![u8::MAX; len];
+    table[0] = 0;
+
+    let mut queue = VecDeque::from([0]);
+    while let Some(index) = queue.pop_front() {
+        for m in 0..moves {
+            let next = neighbor(index, m);
+            if table[next] == u8::MAX {
+                table[next] = table[index] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    table
+}
+
+/// Builds a move table for a coordinate with `len` values, using `set` to create a cube with a given value
+/// and `get` to read the value of a cube.
+fn move_table<const N: usize>(
+    len: usize,
+    moves: &[CubieCube; N],
+    set: impl Fn(&mut CubieCube, u16),
+    get: impl Fn(&CubieCube) -> u16,
+) -> Vec<[u16; N]> {
+    (0..len as u16)
+        .map(|value| {
+            let mut cube = CubieCube::SOLVED;
+            set(&mut cube, value);
+            moves.map(|m| get(&cube.multiply(&m)))
+        })
+        .collect()
+}
+
+/// Returns whether `m` may follow `last`: turning the same face twice in a row, or opposite faces in both orders
+/// never leads to a shorter solution.
+fn allowed(last: Option<usize>, m: usize) -> bool {
+    match last {
+        Some(last) => {
+            let (face, last_face) = (m / 3, last / 3);
+            face != last_face && !(face % 3 == last_face % 3 && face < last_face)
+        }
+        None => true,
+    }
+}
+
+/// A near-optimal solver using Kociemba's two-phase algorithm.
+///
+/// Phase 1 brings the cube into the subgroup <U, D, R2, L2, F2, B2>, where all pieces are oriented and the
+/// edges of the middle slice are in that slice. Phase 2 then solves the cube using only moves of that subgroup.
+/// Both phases are iterative deepening searches with small pruning tables, and every phase 1 solution is
+/// completed to a full solution, so longer searches keep finding shorter solutions. Once phase 1 solutions
+/// are as long as the best solution, there is no shorter one left to find.
+///
+/// Like [`crate::ida::IdaSolver`], lengths are measured in the half turn metric.
+#[derive(Debug)]
+pub struct TwoPhaseSolver {
+    twist_moves: Vec<[u16; MOVE_COUNT]>,
+    flip_moves: Vec<[u16; MOVE_COUNT]>,
+    slice_sorted_moves: Vec<[u16; MOVE_COUNT]>,
+    corner_permutation_moves: Vec<[u16; PHASE2_MOVES.len()]>,
+    ud_edge_moves: Vec<[u16; PHASE2_MOVES.len()]>,
+    /// Phase 1 distances indexed by slice combination and twist.
+    slice_twist_prune: Vec<u8>,
+    /// Phase 1 distances indexed by slice combination and flip.
+    slice_flip_prune: Vec<u8>,
+    /// Phase 2 distances indexed by slice permutation and corner permutation.
+    slice_corner_prune: Vec<u8>,
+    /// Phase 2 distances indexed by slice permutation and permutation of the other edges.
+    slice_edge_prune: Vec<u8>,
+}
+
+impl TwoPhaseSolver {
+    pub fn new() -> Self {
+        let moves = FACE_TURNS;
+        let phase2_moves = PHASE2_MOVES.map(|m| moves[m]);
+
+        let twist_moves = move_table(TWISTS, &moves, CubieCube::set_twist, CubieCube::twist);
+        let flip_moves = move_table(FLIPS, &moves, CubieCube::set_flip, CubieCube::flip);
+        let slice_sorted_moves = move_table(
+            SLICE_SORTED,
+            &moves,
+            CubieCube::set_slice_sorted,
+            CubieCube::slice_sorted,
+        );
+        let corner_permutation_moves = move_table(
+            CORNER_PERMUTATIONS,
+            &phase2_moves,
+            CubieCube::set_corner_permutation,
+            CubieCube::corner_permutation,
+        );
+        let ud_edge_moves = move_table(
+            UD_EDGE_PERMUTATIONS,
+            &phase2_moves,
+            CubieCube::set_ud_edges,
+            CubieCube::ud_edges,
+        );
+
+        let slice_twist_prune = prune_table(SLICE_COMBINATIONS * TWISTS, MOVE_COUNT, |i, m| {
+            let slice = slice_sorted_moves[i / TWISTS * 24][m] as usize / 24;
+            slice * TWISTS + twist_moves[i % TWISTS][m] as usize
+        });
+        let slice_flip_prune = prune_table(SLICE_COMBINATIONS * FLIPS, MOVE_COUNT, |i, m| {
+            let slice = slice_sorted_moves[i / FLIPS * 24][m] as usize / 24;
+            slice * FLIPS + flip_moves[i % FLIPS][m] as usize
+        });
+        let slice_corner_prune = prune_table(
+            SLICE_PERMUTATIONS * CORNER_PERMUTATIONS,
+            PHASE2_MOVES.len(),
+            |i, m| {
+                let slice = slice_sorted_moves[i / CORNER_PERMUTATIONS][PHASE2_MOVES[m]] as usize;
+                slice * CORNER_PERMUTATIONS
+                    + corner_permutation_moves[i % CORNER_PERMUTATIONS][m] as usize
+            },
+        );
+        let slice_edge_prune = prune_table(
+            SLICE_PERMUTATIONS * UD_EDGE_PERMUTATIONS,
+            PHASE2_MOVES.len(),
+            |i, m| {
+                let slice = slice_sorted_moves[i / UD_EDGE_PERMUTATIONS][PHASE2_MOVES[m]] as usize;
+                slice * UD_EDGE_PERMUTATIONS + ud_edge_moves[i % UD_EDGE_PERMUTATIONS][m] as usize
+            },
+        );
+
+        Self {
+            twist_moves,
+            flip_moves,
+            slice_sorted_moves,
+            corner_permutation_moves,
+            ud_edge_moves,
+            slice_twist_prune,
+            slice_flip_prune,
+            slice_corner_prune,
+            slice_edge_prune,
+        }
+    }
+
+    /// Searches for a solution of `cube` that is at most `max_length` face turns long.
+    ///
+    /// Once a solution is found, the search keeps looking for shorter ones until `timeout` has passed or
+    /// there are none left, and then returns the shortest one. Returns `None` if there is no solution of
+    /// at most `max_length` moves or `timeout` passes before one is found, and an error if `cube` can't be
+    /// solved at all.
+    pub fn solve(
+        &self,
+        cube: Cube,
+        max_length: u8,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Move>>, ValidationError> {
+        self.search(cube, max_length, 0, timeout, false)
+    }
+
+    /// Returns the first solution of `cube` that is at most `max_length` face turns long, without looking
+    /// for shorter ones, so the solution doesn't depend on how fast the search is.
+    ///
+    /// As long as no such solution is found, the search keeps looking for shorter solutions until `timeout`
+    /// has passed, and then returns the shortest one found so far. Returns an error if `cube` can't be
+    /// solved at all.
+    pub fn first_solution(
+        &self,
+        cube: Cube,
+        max_length: u8,
+        timeout: Duration,
+    ) -> Result<Vec<Move>, ValidationError> {
+        let solution = self.search(cube, u8::MAX, max_length, timeout, true)?;
+        Ok(solution.expect("every cube has a two-phase solution"))
+    }
+
+    /// Finds the shortest solution of at most `max_length` moves until the search runs out of time or
+    /// finds one of at most `good_enough` moves. With `needs_solution`, the search only stops for the
+    /// timeout once it has found a solution.
+    fn search(
+        &self,
+        cube: Cube,
+        max_length: u8,
+        good_enough: u8,
+        timeout: Duration,
+        needs_solution: bool,
+    ) -> Result<Option<Vec<Move>>, ValidationError> {
+        cube.validate()?;
+        let cubies = CubieCube::relative_to_centers(&cube)?;
+        let mut search = Search {
+            solver: self,
+            cubies,
+            max_length,
+            good_enough,
+            deadline: Instant::now() + timeout,
+            needs_solution,
+            path: Vec::new(),
+            best: None,
+            nodes: 0,
+            done: false,
+        };
+
+        let (twist, flip, slice) = (cubies.twist(), cubies.flip(), cubies.slice_sorted());
+        for depth in self.phase1_estimate(twist, flip, slice)..=MAX_PHASE1_DEPTH.min(max_length) {
+            if search
+                .best
+                .as_ref()
+                .is_some_and(|best| depth as usize >= best.len())
+            {
+                // every phase 1 solution this long leads to a longer solution than the best one
+                break;
+            }
+
+            search.phase1(twist, flip, slice, depth);
+            if search.done {
+                break;
+            }
+        }
+
+        Ok(search
+            .best
+            .map(|best| best.into_iter().map(to_move).collect()))
+    }
+
+    /// Like [`TwoPhaseSolver::solve`], but solves all `cubes` in parallel on rayon's global thread pool.
+    /// The solutions are in the same order as the cubes, and each search gets the whole `timeout`.
+    pub fn solve_batch(
+        &self,
+        cubes: &[Cube],
+        max_length: u8,
+        timeout: Duration,
+    ) -> Vec<Result<Option<Vec<Move>>, ValidationError>> {
+        cubes
+            .par_iter()
+            .map(|&cube| self.solve(cube, max_length, timeout))
+            .collect()
+    }
+
+    fn phase1_estimate(&self, twist: u16, flip: u16, slice: u16) -> u8 {
+        let slice = slice as usize / 24;
+        self.slice_twist_prune[slice * TWISTS + twist as usize]
+            .max(self.slice_flip_prune[slice * FLIPS + flip as usize])
+    }
+
+    fn phase2_estimate(&self, corners: u16, edges: u16, slice: u16) -> u8 {
+        let slice = slice as usize;
+        self.slice_corner_prune[slice * CORNER_PERMUTATIONS + corners as usize]
+            .max(self.slice_edge_prune[slice * UD_EDGE_PERMUTATIONS + edges as usize])
+    }
+}
+
+impl Default for TwoPhaseSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The state of a single call to [`TwoPhaseSolver::solve`].
+struct Search<'a> {
+    solver: &'a TwoPhaseSolver,
+    cubies: CubieCube,
+    /// Longer solutions are never looked at.
+    max_length: u8,
+    /// The search stops at the first solution that is at most this long.
+    good_enough: u8,
+    deadline: Instant,
+    /// Whether the search keeps going past the deadline until it has found a solution.
+    needs_solution: bool,
+    /// The face turns of the current phase 1 and phase 2 candidate.
+    path: Vec<usize>,
+    best: Option<Vec<usize>>,
+    nodes: usize,
+    done: bool,
+}
+
+impl Search<'_> {
+    fn phase1(&mut self, twist: u16, flip: u16, slice: u16, togo: u8) {
+        self.nodes += 1;
+        let may_stop = self.best.is_some() || !self.needs_solution;
+        if self.nodes.is_multiple_of(4096) && may_stop && Instant::now() >= self.deadline {
+            self.done = true;
+        }
+        if self.done {
+            return;
+        }
+
+        if togo == 0 {
+            // a phase 1 solution ending in a phase 2 move has already been tried as a shorter one
+            let ends_in_phase2 = self.path.last().is_some_and(|m| PHASE2_MOVES.contains(m));
+            if !ends_in_phase2 {
+                self.start_phase2();
+            }
+            return;
+        }
+
+        let solver = self.solver;
+        for m in 0..MOVE_COUNT {
+            if !allowed(self.path.last().copied(), m) {
+                continue;
+            }
+
+            let twist = solver.twist_moves[twist as usize][m];
+            let flip = solver.flip_moves[flip as usize][m];
+            let slice = solver.slice_sorted_moves[slice as usize][m];
+            let estimate = solver.phase1_estimate(twist, flip, slice);
+            if estimate >= togo {
+                continue;
+            }
+
+            self.path.push(m);
+            self.phase1(twist, flip, slice, togo - 1);
+            self.path.pop();
+            if self.done {
+                return;
+            }
+        }
+    }
+
+    fn start_phase2(&mut self) {
+        let cube = self
+            .path
+            .iter()
+            .fold(self.cubies, |cube, &m| cube.multiply(&FACE_TURNS[m]));
+
+        let phase1_length = self.path.len() as u8;
+        // only solutions that are shorter than the best one are worth finding
+        let max_length = match &self.best {
+            Some(best) => best.len() as u8 - 1,
+            None => self.max_length,
+        };
+        let Some(max_depth) = max_length.checked_sub(phase1_length) else {
+            return;
+        };
+
+        let (corners, edges, slice) = (
+            cube.corner_permutation(),
+            cube.ud_edges(),
+            cube.slice_sorted(),
+        );
+        let estimate = self.solver.phase2_estimate(corners, edges, slice);
+        for depth in estimate..=max_depth.min(MAX_PHASE2_DEPTH) {
+            if self.phase2(corners, edges, slice, depth) {
+                if self.path.len() <= self.good_enough as usize {
+                    self.done = true;
+                }
+                self.best = Some(self.path.clone());
+                self.path.truncate(phase1_length as usize);
+                return;
+            }
+        }
+    }
+
+    /// Returns true once a solution is found, leaving the phase 2 moves on `path`.
+    fn phase2(&mut self, corners: u16, edges: u16, slice: u16, togo: u8) -> bool {
+        if togo == 0 {
+            return corners == 0 && edges == 0 && slice == 0;
+        }
+
+        let solver = self.solver;
+        for (i, &m) in PHASE2_MOVES.iter().enumerate() {
+            if !allowed(self.path.last().copied(), m) {
+                continue;
+            }
+
+            let corners = solver.corner_permutation_moves[corners as usize][i];
+            let edges = solver.ud_edge_moves[edges as usize][i];
+            let slice = solver.slice_sorted_moves[slice as usize][m];
+            if solver.phase2_estimate(corners, edges, slice) >= togo {
+                continue;
+            }
+
+            self.path.push(m);
+            if self.phase2(corners, edges, slice, togo - 1) {
+                return true;
+            }
+            self.path.pop();
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+    use crate::turn::{Move::*, Turnable};
+
+    fn solver() -> &'static TwoPhaseSolver {
+        static SOLVER: OnceLock<TwoPhaseSolver> = OnceLock::new();
+        SOLVER.get_or_init(TwoPhaseSolver::new)
+    }
+
+    fn assert_solves(
+        mut cube: Cube,
+        solution: Result<Option<Vec<Move>>, ValidationError>,
+    ) -> Vec<Move> {
+        let solution = solution.unwrap().expect("no solution found");
+
+        cube.perform_all(&solution);
+        assert_eq!(cube, Cube::default());
+        solution
+    }
+
+    #[test]
+    fn already_solved() {
+        let solution = solver().solve(Cube::default(), 20, Duration::from_secs(1));
+        assert_eq!(solution, Ok(Some(Vec::new())));
+    }
+
+    #[test]
+    fn unsolvable() {
+        let mut cubies = CubieCube::SOLVED;
+        cubies.ep.swap(0, 1);
+
+        let solution = solver().solve(cubies.into(), 20, Duration::from_secs(1));
+        assert_eq!(solution, Err(ValidationError::Parity));
+    }
+
+    #[test]
+    fn sune() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, RPrime, U, R, U, U, RPrime]);
+
+        let solution = assert_solves(cube, solver().solve(cube, 7, Duration::from_secs(10)));
+        assert_eq!(solution.len(), 7);
+    }
+
+    #[test]
+    fn batch() {
+        let mut sune = Cube::default();
+        sune.perform_all(&[R, U, RPrime, U, R, U, U, RPrime]);
+        let mut cubies = CubieCube::SOLVED;
+        cubies.ep.swap(0, 1);
+
+        let cubes = [sune, cubies.into(), Cube::default()];
+        let solutions = solver().solve_batch(&cubes, 20, Duration::from_millis(100));
+        assert_solves(sune, solutions[0].clone());
+        assert_eq!(solutions[1], Err(ValidationError::Parity));
+        assert_eq!(solutions[2], Ok(Some(Vec::new())));
+    }
+
+    #[test]
+    fn random_scramble() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[
+            D, D, BPrime, L, L, U, F, F, R, UPrime, B, B, DPrime, L, F, F, U, R, R, BPrime, D, D,
+            F, LPrime, U, U, B, RPrime, F, F, D,
+        ]);
+
+        let solution = assert_solves(cube, solver().solve(cube, 22, Duration::from_millis(100)));
+        assert!(solution.len() <= 22);
+    }
+
+    #[test]
+    fn superflip() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[
+            U, R, R, F, B, R, B, B, R, U, U, L, B, B, R, UPrime, DPrime, R, R, F, RPrime, L, B, B,
+            U, U, F, F,
+        ]);
+
+        // no solution is shorter, but the search can't tell
+        let solution = solver().first_solution(cube, 20, Duration::from_secs(60));
+        let solution = assert_solves(cube, solution.map(Some));
+        assert_eq!(solution.len(), 20);
+    }
+
+    #[test]
+    fn keeps_improving() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[R, U, FPrime, L, L, D, BPrime, R, UPrime, F, L, DPrime, B, B]);
+
+        let first = solver().first_solution(cube, 30, Duration::ZERO);
+        let first = assert_solves(cube, first.map(Some));
+        let improved = assert_solves(cube, solver().solve(cube, 30, Duration::from_millis(500)));
+        assert!(improved.len() < first.len());
+        assert!(improved.len() >= 12);
+
+        // there is no solution that short, so the search gives up without one
+        assert_eq!(solver().solve(cube, 5, Duration::from_secs(10)), Ok(None));
+    }
+
+    #[test]
+    fn times_out_without_solution() {
+        let mut cube = Cube::default();
+        cube.perform_all(&[
+            U, R, R, F, B, R, B, B, R, U, U, L, B, B, R, UPrime, DPrime, R, R, F, RPrime, L, B, B,
+            U, U, F, F,
+        ]);
+
+        // the superflip has no solution of 19 moves, which would take far too long to rule out
+        let start = Instant::now();
+        assert_eq!(
+            solver().solve(cube, 19, Duration::from_millis(100)),
+            Ok(None)
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn optimal_when_short() {
+        // short solutions are found long before the timeout, and shown to be the shortest
+        for (scramble, length) in [
+            (&[RPrime][..], 1),
+            (&[R, U, RPrime, UPrime], 4),
+            (&[F, R, U, RPrime, UPrime, FPrime], 6),
+        ] {
+            let mut cube = Cube::default();
+            cube.perform_all(scramble);
+            let solution = assert_solves(cube, solver().solve(cube, 22, Duration::from_secs(60)));
+            assert_eq!(solution.len(), length, "{scramble:?}");
+        }
+    }
+}